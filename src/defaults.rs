@@ -1,93 +1,190 @@
+use std::{collections::BTreeMap, fs};
+
 use crate::{append_to_path, output::write_file};
 
+macro_rules! default_files {
+    ($($name:literal),* $(,)?) => {
+        /// Every script and template moho ships with, by file name.
+        pub const DEFAULT_FILES: &[(&str, &str)] = &[
+            $(($name, include_str!(concat!("default/", $name)))),*
+        ];
+    };
+}
+
+default_files!(
+    "base.rhai",
+    "AActor.rhai",
+    "UENUM.rhai",
+    "USTRUCT.rhai",
+    "aactor-template-cpp.txt",
+    "aactor-template-h.txt",
+    "access-section-template.txt",
+    "field-declaration-template.txt",
+    "field-definition-template.txt",
+    "field-getter-template.txt",
+    "field-setter-template.txt",
+    "field-name-template.txt",
+    "type-template-array.txt",
+    "type-template-bool.txt",
+    "type-template-char.txt",
+    "type-template-class.txt",
+    "type-template-float.txt",
+    "type-template-generic.txt",
+    "type-template-integer.txt",
+    "type-template-matrix.txt",
+    "type-template-pointer.txt",
+    "type-template-reference.txt",
+    "type-template-string.txt",
+    "method-declaration-template.txt",
+    "method-definition-template.txt",
+    "component-creation-template.txt",
+    "component-attachment-template.txt",
+    "field-initialization-template.txt",
+    "replication-template-h.txt",
+    "replication-template-cpp.txt",
+    "replicated-property-template.txt",
+    "replicated-property-condition-template.txt",
+    "onrep-declaration-template.txt",
+    "onrep-definition-template.txt",
+    "rpc-implementation-template.txt",
+    "rpc-validate-template.txt",
+    "uenum-template-h.txt",
+    "enum-entry-template.txt",
+    "ustruct-template-h.txt",
+    "ustruct-template-cpp.txt",
+    "struct-method-declaration-template.txt",
+);
+
+/// Written next to copied default files, with the fingerprint of the built-in version each copy was made from.
+pub const DEFAULTS_MANIFEST: &str = ".moho-defaults";
+
 pub fn copy_default_files(moho_path: &str) {
-    write_file(
-        append_to_path(moho_path, "base.rhai"),
-        include_str!("default/base.rhai").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "AActor.rhai"),
-        include_str!("default/AActor.rhai").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "aactor-template-cpp.txt"),
-        include_str!("default/aactor-template-cpp.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "aactor-template-h.txt"),
-        include_str!("default/aactor-template-h.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "field-declaration-template.txt"),
-        include_str!("default/field-declaration-template.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "field-definition-template.txt"),
-        include_str!("default/field-definition-template.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "field-name-template.txt"),
-        include_str!("default/field-name-template.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-array.txt"),
-        include_str!("default/type-template-array.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-bool.txt"),
-        include_str!("default/type-template-bool.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-char.txt"),
-        include_str!("default/type-template-char.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-class.txt"),
-        include_str!("default/type-template-class.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-float.txt"),
-        include_str!("default/type-template-float.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-integer.txt"),
-        include_str!("default/type-template-integer.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-matrix.txt"),
-        include_str!("default/type-template-matrix.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-pointer.txt"),
-        include_str!("default/type-template-pointer.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-ref.txt"),
-        include_str!("default/type-template-ref.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "type-template-string.txt"),
-        include_str!("default/type-template-string.txt").to_owned(),
-    );
-
-    write_file(
-        append_to_path(moho_path, "method-declaration-template.txt"),
-        include_str!("default/method-declaration-template.txt").to_owned(),
-    );
+    let mut manifest = String::new();
+    for (name, content) in DEFAULT_FILES {
+        write_file(append_to_path(moho_path, name), content.to_string());
+        manifest += &format!("{} {:016x}\n", name, fingerprint(content));
+    }
+    write_file(append_to_path(moho_path, DEFAULTS_MANIFEST), manifest);
+}
+
+/// FNV-1a over the text with `\r\n` read as `\n`, so it's the same on every platform and every build.
+fn fingerprint(content: &str) -> u64 {
+    content
+        .replace("\r\n", "\n")
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// A copy of a built-in file in the user config folder that hides a newer built-in version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleCopy {
+    pub name: &'static str,
+    /// Whether the copy was changed after it was made; unchanged ones can just be deleted.
+    pub edited: bool,
+}
+
+/// The files in `moho_path` that were copied from a built-in file that has changed since. Copies made before
+/// the manifest existed have nothing to compare with, so any of them that differs from the built-in counts.
+pub fn stale_copies(moho_path: &str) -> Vec<StaleCopy> {
+    let manifest =
+        fs::read_to_string(append_to_path(moho_path, DEFAULTS_MANIFEST)).unwrap_or_default();
+    let copied_from = manifest
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(name, hash)| Some((name, u64::from_str_radix(hash, 16).ok()?)))
+        .collect::<BTreeMap<_, _>>();
+
+    DEFAULT_FILES
+        .iter()
+        .filter_map(|(name, content)| {
+            let copy = fs::read_to_string(append_to_path(moho_path, name)).ok()?;
+            let builtin = fingerprint(content);
+            let base = copied_from.get(name).copied();
+            if fingerprint(&copy) == builtin || base == Some(builtin) {
+                return None;
+            }
+
+            Some(StaleCopy {
+                name,
+                edited: base != Some(fingerprint(&copy)),
+            })
+        })
+        .collect()
+}
+
+/// The built-in version of a script or template, used when no folder on the search path has it.
+pub fn builtin_file(name: &str) -> Option<&'static str> {
+    DEFAULT_FILES
+        .iter()
+        .find(|(file, _)| *file == name)
+        .map(|(_, content)| *content)
+}
+
+#[cfg(test)]
+mod defaults_tests {
+    use std::fs;
+
+    use crate::defaults::{
+        copy_default_files, fingerprint, stale_copies, StaleCopy, DEFAULTS_MANIFEST,
+    };
+
+    #[test]
+    fn copies_of_older_built_ins_are_stale() {
+        let root = std::env::temp_dir().join(format!("moho-defaults-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.to_str().unwrap();
+
+        // no folder, and a fresh copy, hide nothing
+        assert_eq!(stale_copies(dir), vec![]);
+        copy_default_files(dir);
+        assert_eq!(stale_copies(dir), vec![]);
+
+        // an edited copy of the current version is what the folder is for
+        fs::write(root.join("field-name-template.txt"), "{{name}}").unwrap();
+        assert_eq!(stale_copies(dir), vec![]);
+
+        // copies of an older version, unchanged and edited since
+        let manifest = fs::read_to_string(root.join(DEFAULTS_MANIFEST)).unwrap();
+        let old = format!("{:016x}", fingerprint("// old"));
+        let manifest = manifest
+            .lines()
+            .map(|line| match line.split_once(' ') {
+                Some((name @ ("UENUM.rhai" | "field-name-template.txt"), _)) => {
+                    format!("{} {}", name, old)
+                }
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(root.join(DEFAULTS_MANIFEST), manifest).unwrap();
+        fs::write(root.join("UENUM.rhai"), "// old").unwrap();
+        assert_eq!(
+            stale_copies(dir),
+            vec![
+                StaleCopy {
+                    name: "UENUM.rhai",
+                    edited: false
+                },
+                StaleCopy {
+                    name: "field-name-template.txt",
+                    edited: true
+                },
+            ]
+        );
+
+        // copies made before the manifest existed
+        fs::remove_file(root.join(DEFAULTS_MANIFEST)).unwrap();
+        fs::remove_file(root.join("UENUM.rhai")).unwrap();
+        assert_eq!(
+            stale_copies(dir),
+            vec![StaleCopy {
+                name: "field-name-template.txt",
+                edited: true
+            }]
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,135 @@
+use std::{fmt::Display, fs};
+
+use crate::{defaults::builtin_file, error::MohoError};
+
+/// An error ready to be shown to a person: the error itself, plus the line of source it points at.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub error: MohoError,
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    /// Reads the offending line from the error's file; use `with_source` if the source is already in memory.
+    pub fn new(error: MohoError) -> Self {
+        let source = error
+            .file
+            .as_ref()
+            .and_then(|file| {
+                fs::read_to_string(file).ok().or_else(|| {
+                    // errors in built-in scripts point at `<builtin>/name`
+                    file.strip_prefix("<builtin>")
+                        .ok()
+                        .and_then(|name| builtin_file(name.to_str()?))
+                        .map(|content| content.to_string())
+                })
+            })
+            .unwrap_or_default();
+
+        Diagnostic::with_source(error, &source)
+    }
+
+    pub fn with_source(error: MohoError, source: &str) -> Self {
+        let source_line = error.location.as_ref().and_then(|location| {
+            source
+                .lines()
+                .nth(location.line.saturating_sub(1))
+                .map(|line| line.trim_end().to_string())
+        });
+
+        Diagnostic { error, source_line }
+    }
+
+    /// How many carets go under the source line: the error's range, cut off at the end of the line.
+    fn width(&self) -> usize {
+        let (Some(location), Some(source_line)) = (&self.error.location, &self.source_line) else {
+            return 1;
+        };
+
+        let available = source_line
+            .chars()
+            .count()
+            .saturating_sub(location.col.saturating_sub(1));
+        location.range.len().clamp(1, available.max(1))
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let error = &self.error;
+        writeln!(
+            f,
+            "error[{}]: {}: {}",
+            error.code,
+            error.code.title(),
+            error.message
+        )?;
+
+        let file = error
+            .file
+            .as_ref()
+            .map(|f| f.display().to_string())
+            .unwrap_or("<input>".into());
+
+        let (Some(location), Some(source_line)) = (&error.location, &self.source_line) else {
+            writeln!(f, "  --> {}", file)?;
+            if let Some(hint) = error.code.hint() {
+                writeln!(f, "  = hint: {}", hint)?;
+            }
+            return Ok(());
+        };
+
+        let gutter = " ".repeat(location.line.to_string().len());
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter, file, location.line, location.col
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", location.line, source_line)?;
+        writeln!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(location.col.saturating_sub(1)),
+            "^".repeat(self.width())
+        )?;
+
+        if let Some(hint) = error.code.hint() {
+            writeln!(f, "{} = hint: {}", gutter, hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod diagnostics_tests {
+    use std::path::Path;
+
+    use crate::{diagnostics::Diagnostic, error::ErrorCode, parser::MohoParser};
+
+    #[test]
+    fn points_at_syntax_error() {
+        pest::set_error_detail(true);
+        let source = "class A\n{\n    float Health = 10\n    int X;\n}\n";
+        let error = MohoParser::apply(source)
+            .expect_err("should not parse")
+            .in_file(Path::new("a.moho"));
+        assert_eq!(error.code, ErrorCode::Syntax);
+
+        let diagnostic = Diagnostic::with_source(error, source);
+        let location = diagnostic
+            .error
+            .location
+            .as_ref()
+            .expect("should have a location");
+        assert_eq!((location.line, location.col), (4, 5));
+        assert!(diagnostic.error.message.contains("`;`"));
+
+        let report = diagnostic.to_string();
+        assert!(report.starts_with("error[M0001]: syntax error: expected"));
+        assert!(report.contains("--> a.moho:4:5"));
+        assert!(report.contains("4 |     int X;\n  |     ^\n"));
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use similar::TextDiff;
+
+/// A unified diff from what is on disk (`None` if the file doesn't exist yet) to what moho would generate.
+pub fn unified_diff(path: &Path, on_disk: Option<&str>, generated: &str) -> String {
+    let path = path.display().to_string();
+    let old_header = if on_disk.is_some() {
+        format!("a/{}", path.trim_start_matches('/'))
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_header = format!("b/{}", path.trim_start_matches('/'));
+
+    TextDiff::from_lines(on_disk.unwrap_or_default(), generated)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &new_header)
+        .to_string()
+}
+
+/// Colors a unified diff for the terminal: removals red, additions green, hunk headers cyan.
+pub fn colorize_diff(diff: &str) -> String {
+    diff.split_inclusive('\n')
+        .map(|line| {
+            let color = if line.starts_with("---") || line.starts_with("+++") {
+                "\x1b[1m"
+            } else if line.starts_with('-') {
+                "\x1b[31m"
+            } else if line.starts_with('+') {
+                "\x1b[32m"
+            } else if line.starts_with("@@") {
+                "\x1b[36m"
+            } else {
+                return line.to_string();
+            };
+
+            let (text, newline) = match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            };
+            format!("{}{}\x1b[0m{}", color, text, newline)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use std::path::Path;
+
+    use crate::diff::{colorize_diff, unified_diff};
+
+    #[test]
+    fn diff_changed_and_missing_files() {
+        let diff = unified_diff(Path::new("A.h"), Some("a\nb\nc\n"), "a\nB\nc\n");
+        assert!(diff.starts_with("--- a/A.h\n+++ b/A.h\n@@ -1,3 +1,3 @@\n"));
+        assert!(diff.contains("-b\n+B\n"));
+
+        let diff = unified_diff(Path::new("A.h"), None, "a\n");
+        assert!(diff.starts_with("--- /dev/null\n+++ b/A.h\n"));
+        assert!(diff.contains("+a\n"));
+
+        let colored = colorize_diff("@@ -1 +1 @@\n-b\n+B\n c\n");
+        assert_eq!(
+            colored,
+            "\x1b[36m@@ -1 +1 @@\x1b[0m\n\x1b[31m-b\x1b[0m\n\x1b[32m+B\x1b[0m\n c\n"
+        );
+    }
+}
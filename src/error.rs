@@ -0,0 +1,280 @@
+use std::{
+    fmt::Display,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pest::{Position, Span};
+
+/// Stable codes for everything that can go wrong, so tools can filter errors without matching on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCode {
+    Syntax,
+    UnknownType,
+    InvalidClass,
+    InvalidEnum,
+    InvalidDeclaration,
+    InvalidField,
+    InvalidArgument,
+    DuplicateDefinition,
+    InvalidMethod,
+    InvalidProperty,
+    InvalidValue,
+    Script,
+    Io,
+    Config,
+}
+
+impl ErrorCode {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::Syntax => "M0001",
+            ErrorCode::UnknownType => "M0101",
+            ErrorCode::InvalidClass => "M0201",
+            ErrorCode::InvalidEnum => "M0202",
+            ErrorCode::InvalidDeclaration => "M0203",
+            ErrorCode::InvalidField => "M0204",
+            ErrorCode::InvalidArgument => "M0205",
+            ErrorCode::DuplicateDefinition => "M0206",
+            ErrorCode::InvalidMethod => "M0207",
+            ErrorCode::InvalidProperty => "M0301",
+            ErrorCode::InvalidValue => "M0302",
+            ErrorCode::Script => "M0501",
+            ErrorCode::Io => "M0601",
+            ErrorCode::Config => "M0701",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ErrorCode::Syntax => "syntax error",
+            ErrorCode::UnknownType => "unknown type",
+            ErrorCode::InvalidClass => "invalid class",
+            ErrorCode::InvalidEnum => "invalid enum",
+            ErrorCode::InvalidDeclaration => "invalid declaration",
+            ErrorCode::InvalidField => "invalid field",
+            ErrorCode::InvalidArgument => "invalid argument",
+            ErrorCode::DuplicateDefinition => "duplicate definition",
+            ErrorCode::InvalidMethod => "invalid method",
+            ErrorCode::InvalidProperty => "invalid property",
+            ErrorCode::InvalidValue => "invalid value",
+            ErrorCode::Script => "script error",
+            ErrorCode::Io => "io error",
+            ErrorCode::Config => "invalid config",
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ErrorCode::Syntax => Some("every field and method ends with `;`, and every `{` and `[` needs a matching `}` and `]`"),
+            ErrorCode::UnknownType => Some("types are identifiers, optionally with generic arguments and `*`, `**`, `&`, `[]`, or `[][]`"),
+            ErrorCode::InvalidClass => Some("classes and structs are written as `class Name : Parent { ... }`"),
+            ErrorCode::InvalidEnum => Some("enums are written as `enum Name : uint8 { A, B = 3 }`"),
+            ErrorCode::InvalidDeclaration => Some("a class body can only contain fields, methods, and `{ ... }` blocks"),
+            ErrorCode::InvalidField => Some("fields are written as `Type Name;` or `Type Name = value;`"),
+            ErrorCode::InvalidArgument => Some("arguments are written as `Type Name`, optionally followed by `= value`"),
+            ErrorCode::DuplicateDefinition => Some("class, struct, and enum names have to be unique across every .moho file in the project"),
+            ErrorCode::InvalidMethod => Some("net functions (`Server`, `Client`, `NetMulticast`) run on another machine, so they can't return anything and have to be `void`"),
+            ErrorCode::InvalidProperty => Some("properties are written as `[Name]` or `[Name=value]`, separated by commas"),
+            ErrorCode::InvalidValue => Some("values can be integers, floats, bools, chars, string literals, names, or nullptr"),
+            ErrorCode::Script => Some("this error comes from the script, not from your .moho file"),
+            ErrorCode::Io => None,
+            ErrorCode::Config => Some("moho.toml can set `search_path`, `include`, `exclude`, `output`, `default_script`, `field_prefix`, and `headers`"),
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A byte range in a source file, with the (1-based) line and column it starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub range: Range<usize>,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Location {
+    pub fn from_span(span: &Span<'_>) -> Self {
+        let (line, col) = span.start_pos().line_col();
+        Location {
+            range: span.start()..span.end(),
+            line,
+            col,
+        }
+    }
+
+    pub fn from_range(input: &str, range: Range<usize>) -> Self {
+        let (line, col) = Position::new(input, range.start)
+            .map(|p| p.line_col())
+            .unwrap_or((1, 1));
+        Location { range, line, col }
+    }
+
+    pub fn from_line_col(input: &str, line: usize, col: usize) -> Self {
+        let start = input
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|l| l.len())
+            .sum::<usize>()
+            + col.saturating_sub(1);
+        Location {
+            range: start..start + 1,
+            line,
+            col,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MohoError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub location: Option<Location>,
+}
+
+impl MohoError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        MohoError {
+            code,
+            message: message.into(),
+            file: None,
+            location: None,
+        }
+    }
+
+    pub fn spanned(code: ErrorCode, message: impl Into<String>, span: Span<'_>) -> Self {
+        MohoError::new(code, message).at(Location::from_span(&span))
+    }
+
+    pub fn script(path: &Path, source: &str, subject: &str, error: &rhai::EvalAltResult) -> Self {
+        let position = error.position();
+        let result = MohoError::new(
+            ErrorCode::Script,
+            format!("while generating {}: {}", subject, error),
+        )
+        .in_file(path);
+
+        match position.line() {
+            Some(line) => result.at(Location::from_line_col(
+                source,
+                line,
+                position.position().unwrap_or(1),
+            )),
+            None => result,
+        }
+    }
+
+    pub fn at(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn in_file(mut self, path: &Path) -> Self {
+        self.file = Some(path.to_path_buf());
+        self
+    }
+}
+
+impl Display for MohoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, &self.location) {
+            (Some(file), Some(location)) => {
+                write!(f, "{}:{}:{}: ", file.display(), location.line, location.col)?
+            }
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            (None, Some(location)) => write!(f, "{}:{}: ", location.line, location.col)?,
+            (None, None) => {}
+        }
+
+        write!(
+            f,
+            "error[{}]: {}: {}",
+            self.code,
+            self.code.title(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for MohoError {}
+
+#[cfg(test)]
+mod error_tests {
+    use std::{collections::BTreeSet, path::Path};
+
+    use crate::{
+        error::{ErrorCode, Location, MohoError},
+        parser::MohoParser,
+    };
+
+    #[test]
+    fn errors_outlive_their_source() {
+        pest::set_error_detail(true);
+        // parsed on another thread, from a string that's gone by the time the errors are read
+        let errors = std::thread::spawn(|| {
+            let source = String::from("class A\n{\n    float Health = 10\n    int X;\n}\n");
+            let (_, errors) = MohoParser::apply_recovering(&source);
+            errors
+                .into_iter()
+                .map(|e| e.in_file(Path::new("a.moho")))
+                .collect::<Vec<_>>()
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!(error.code, ErrorCode::Syntax);
+        assert_eq!(
+            error.location,
+            Some(Location {
+                range: 36..37,
+                line: 4,
+                col: 5
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "a.moho:4:5: error[M0001]: syntax error: expected `;`"
+        );
+
+        // tools can treat it like any other error
+        let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(error.clone());
+        assert_eq!(boxed.to_string(), error.to_string());
+    }
+
+    #[test]
+    fn codes_are_stable_and_unique() {
+        let codes = [
+            ErrorCode::Syntax,
+            ErrorCode::UnknownType,
+            ErrorCode::InvalidClass,
+            ErrorCode::InvalidEnum,
+            ErrorCode::InvalidDeclaration,
+            ErrorCode::InvalidField,
+            ErrorCode::InvalidArgument,
+            ErrorCode::DuplicateDefinition,
+            ErrorCode::InvalidMethod,
+            ErrorCode::InvalidProperty,
+            ErrorCode::InvalidValue,
+            ErrorCode::Script,
+            ErrorCode::Io,
+            ErrorCode::Config,
+        ];
+        let unique = codes.iter().map(|c| c.code()).collect::<BTreeSet<_>>();
+        assert_eq!(unique.len(), codes.len());
+        assert_eq!(ErrorCode::UnknownType.to_string(), "M0101");
+
+        let error = MohoError::new(ErrorCode::Io, "cannot read file");
+        assert_eq!(
+            error.to_string(),
+            "error[M0601]: io error: cannot read file"
+        );
+    }
+}
@@ -49,6 +49,43 @@ mod ast_tests {
         assert!(p.is_ok());
     }
 
+    // struct tests
+
+    #[test]
+    fn parse_struct() {
+        let p = MohoGrammar::parse(Rule::struct_decl, "struct FAbc {}");
+        assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(
+            Rule::struct_decl,
+            "[BlueprintType] struct FRow : FTableRowBase { [EditAnywhere] { float Weight = 1; } }",
+        );
+        assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(Rule::moho, "struct FAbc {} class AAbc : AActor {}");
+        assert!(p.is_ok());
+    }
+
+    // enum tests
+
+    #[test]
+    fn parse_enum() {
+        let p = MohoGrammar::parse(Rule::enum_decl, "enum EAbc { A, B = 3, C }");
+        assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(Rule::enum_decl, "enum EAbc : uint8 { A, B, }");
+        assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(
+            Rule::enum_decl,
+            "[BlueprintType] enum EAbc { [DisplayName=\"First\"] A, B }",
+        );
+        assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(Rule::enum_decl, "enum EAbc { A B }");
+        assert!(p.is_err());
+    }
+
     #[test]
     fn parse_declarations() {
         let p = MohoGrammar::parse(Rule::body, "int a; char b = 5; bool x = false;");
@@ -72,6 +109,13 @@ mod ast_tests {
         // and arrays
         let p = MohoGrammar::parse(Rule::body, "SimpleController[] bindings;");
         assert!(p.is_ok());
+
+        // and generics, nested too
+        let p = MohoGrammar::parse(
+            Rule::body,
+            "TMap<FName, int32> Scores; TArray<TSoftObjectPtr<UTexture2D>> Icons; TSubclassOf<AActor>* Spawn;",
+        );
+        assert!(p.is_ok());
     }
 
     #[test]
@@ -81,6 +125,9 @@ mod ast_tests {
 
         let p = MohoGrammar::parse(Rule::class, include_str!("../assets/multi_classes.moho"));
         assert!(p.is_ok());
+
+        let p = MohoGrammar::parse(Rule::moho, include_str!("../assets/enums.moho"));
+        assert!(p.is_ok());
     }
 
     #[test]
@@ -89,5 +136,11 @@ mod ast_tests {
             int32 PlayerIndex);");
         assert!(p.is_ok());
         println!("{:?}", p);
+
+        let p = MohoGrammar::parse(
+            Rule::method_decl,
+            "void SetScores(const TMap<FName, int32>& Scores, TArray<AActor*> Actors);",
+        );
+        assert!(p.is_ok());
     }
 }
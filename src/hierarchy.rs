@@ -0,0 +1,246 @@
+/// Common engine classes as (class, parent, header), so a class whose superclass has no script
+/// still gets the script of its nearest known ancestor, and types used in a class get the right include.
+pub const ENGINE_CLASSES: &[(&str, Option<&str>, &str)] = &[
+    ("UObject", None, "UObject/Object.h"),
+    // actors
+    ("AActor", Some("UObject"), "GameFramework/Actor.h"),
+    ("APawn", Some("AActor"), "GameFramework/Pawn.h"),
+    ("ACharacter", Some("APawn"), "GameFramework/Character.h"),
+    ("ADefaultPawn", Some("APawn"), "GameFramework/DefaultPawn.h"),
+    ("AController", Some("AActor"), "GameFramework/Controller.h"),
+    (
+        "APlayerController",
+        Some("AController"),
+        "GameFramework/PlayerController.h",
+    ),
+    ("AAIController", Some("AController"), "AIController.h"),
+    ("AInfo", Some("AActor"), "GameFramework/Info.h"),
+    (
+        "AGameModeBase",
+        Some("AInfo"),
+        "GameFramework/GameModeBase.h",
+    ),
+    (
+        "AGameMode",
+        Some("AGameModeBase"),
+        "GameFramework/GameMode.h",
+    ),
+    (
+        "AGameStateBase",
+        Some("AInfo"),
+        "GameFramework/GameStateBase.h",
+    ),
+    (
+        "AGameState",
+        Some("AGameStateBase"),
+        "GameFramework/GameState.h",
+    ),
+    ("APlayerState", Some("AInfo"), "GameFramework/PlayerState.h"),
+    ("AHUD", Some("AActor"), "GameFramework/HUD.h"),
+    ("ACameraActor", Some("AActor"), "Camera/CameraActor.h"),
+    (
+        "AStaticMeshActor",
+        Some("AActor"),
+        "Engine/StaticMeshActor.h",
+    ),
+    ("ATriggerBase", Some("AActor"), "Engine/TriggerBase.h"),
+    ("ATriggerBox", Some("ATriggerBase"), "Engine/TriggerBox.h"),
+    (
+        "ATriggerSphere",
+        Some("ATriggerBase"),
+        "Engine/TriggerSphere.h",
+    ),
+    // components
+    (
+        "UActorComponent",
+        Some("UObject"),
+        "Components/ActorComponent.h",
+    ),
+    (
+        "USceneComponent",
+        Some("UActorComponent"),
+        "Components/SceneComponent.h",
+    ),
+    (
+        "UPrimitiveComponent",
+        Some("USceneComponent"),
+        "Components/PrimitiveComponent.h",
+    ),
+    (
+        "UShapeComponent",
+        Some("UPrimitiveComponent"),
+        "Components/ShapeComponent.h",
+    ),
+    (
+        "UBoxComponent",
+        Some("UShapeComponent"),
+        "Components/BoxComponent.h",
+    ),
+    (
+        "USphereComponent",
+        Some("UShapeComponent"),
+        "Components/SphereComponent.h",
+    ),
+    (
+        "UCapsuleComponent",
+        Some("UShapeComponent"),
+        "Components/CapsuleComponent.h",
+    ),
+    (
+        "UMeshComponent",
+        Some("UPrimitiveComponent"),
+        "Components/MeshComponent.h",
+    ),
+    (
+        "UStaticMeshComponent",
+        Some("UMeshComponent"),
+        "Components/StaticMeshComponent.h",
+    ),
+    (
+        "USkinnedMeshComponent",
+        Some("UMeshComponent"),
+        "Components/SkinnedMeshComponent.h",
+    ),
+    (
+        "USkeletalMeshComponent",
+        Some("USkinnedMeshComponent"),
+        "Components/SkeletalMeshComponent.h",
+    ),
+    (
+        "UCameraComponent",
+        Some("USceneComponent"),
+        "Camera/CameraComponent.h",
+    ),
+    (
+        "USpringArmComponent",
+        Some("USceneComponent"),
+        "GameFramework/SpringArmComponent.h",
+    ),
+    (
+        "UAudioComponent",
+        Some("USceneComponent"),
+        "Components/AudioComponent.h",
+    ),
+    (
+        "UMovementComponent",
+        Some("UActorComponent"),
+        "GameFramework/MovementComponent.h",
+    ),
+    (
+        "UNavMovementComponent",
+        Some("UMovementComponent"),
+        "GameFramework/NavMovementComponent.h",
+    ),
+    (
+        "UPawnMovementComponent",
+        Some("UNavMovementComponent"),
+        "GameFramework/PawnMovementComponent.h",
+    ),
+    (
+        "UCharacterMovementComponent",
+        Some("UPawnMovementComponent"),
+        "GameFramework/CharacterMovementComponent.h",
+    ),
+    // everything else
+    ("FTableRowBase", None, "Engine/DataTable.h"),
+    ("UVisual", Some("UObject"), "Components/Visual.h"),
+    ("UWidget", Some("UVisual"), "Components/Widget.h"),
+    ("UUserWidget", Some("UWidget"), "Blueprint/UserWidget.h"),
+    ("UDataAsset", Some("UObject"), "Engine/DataAsset.h"),
+    (
+        "UPrimaryDataAsset",
+        Some("UDataAsset"),
+        "Engine/DataAsset.h",
+    ),
+    ("UGameInstance", Some("UObject"), "Engine/GameInstance.h"),
+    ("USaveGame", Some("UObject"), "GameFramework/SaveGame.h"),
+    ("UAnimInstance", Some("UObject"), "Animation/AnimInstance.h"),
+    (
+        "UBlueprintFunctionLibrary",
+        Some("UObject"),
+        "Kismet/BlueprintFunctionLibrary.h",
+    ),
+    (
+        "UDeveloperSettings",
+        Some("UObject"),
+        "Engine/DeveloperSettings.h",
+    ),
+    ("USubsystem", Some("UObject"), "Subsystems/Subsystem.h"),
+    (
+        "UGameInstanceSubsystem",
+        Some("USubsystem"),
+        "Subsystems/GameInstanceSubsystem.h",
+    ),
+    (
+        "UWorldSubsystem",
+        Some("USubsystem"),
+        "Subsystems/WorldSubsystem.h",
+    ),
+];
+
+/// The engine types in `ENGINE_CLASSES` that are declared as `struct`, which a forward declaration has to match.
+const ENGINE_STRUCTS: &[&str] = &["FTableRowBase"];
+
+const PARENT_DIRECTIVE: &str = "// moho:parent ";
+
+pub fn engine_parent(class: &str) -> Option<&'static str> {
+    ENGINE_CLASSES
+        .iter()
+        .find(|(name, _, _)| *name == class)
+        .and_then(|(_, parent, _)| *parent)
+}
+
+pub fn engine_header(class: &str) -> Option<&'static str> {
+    ENGINE_CLASSES
+        .iter()
+        .find(|(name, _, _)| *name == class)
+        .map(|(_, _, header)| *header)
+}
+
+pub fn is_engine_struct(name: &str) -> bool {
+    ENGINE_STRUCTS.contains(&name)
+}
+
+/// The parent a script declares for its class with a `// moho:parent Name` line.
+pub fn declared_parent(script: &str) -> Option<String> {
+    script.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix(PARENT_DIRECTIVE)
+            .map(|parent| parent.trim().to_string())
+            .filter(|parent| !parent.is_empty())
+    })
+}
+
+/// A script that is nothing but comments only declares a parent, and hands the class over to that parent's script.
+pub fn only_declares_parent(script: &str) -> bool {
+    declared_parent(script).is_some()
+        && script
+            .lines()
+            .map(|line| line.trim())
+            .all(|line| line.is_empty() || line.starts_with("//"))
+}
+
+#[cfg(test)]
+mod hierarchy_tests {
+    use crate::hierarchy::{declared_parent, engine_header, engine_parent, only_declares_parent};
+
+    #[test]
+    fn engine_classes_and_declared_parents() {
+        assert_eq!(engine_parent("ACharacter"), Some("APawn"));
+        assert_eq!(engine_parent("UObject"), None);
+        assert_eq!(engine_parent("AMyCharacter"), None);
+        assert_eq!(
+            engine_header("ACharacter"),
+            Some("GameFramework/Character.h")
+        );
+
+        let forward = "// our base character\n// moho:parent ACharacter\n";
+        assert_eq!(declared_parent(forward), Some("ACharacter".into()));
+        assert!(only_declares_parent(forward));
+
+        let script = "// moho:parent APawn\nOutput.write_to(Name + \".h\");\n";
+        assert_eq!(declared_parent(script), Some("APawn".into()));
+        assert!(!only_declares_parent(script));
+        assert!(!only_declares_parent("// just a comment\n"));
+    }
+}
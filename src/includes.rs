@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    hierarchy::{engine_header, is_engine_struct},
+    parser::Block,
+    symbols::SymbolTable,
+};
+
+/// Types every generated header already has through `CoreMinimal.h` and the UObject headers.
+const CORE_TYPES: &[&str] = &[
+    "UObject",
+    "UClass",
+    "FString",
+    "FName",
+    "FText",
+    "FVector",
+    "FVector2D",
+    "FVector4",
+    "FIntPoint",
+    "FIntVector",
+    "FRotator",
+    "FQuat",
+    "FTransform",
+    "FMatrix",
+    "FBox",
+    "FColor",
+    "FLinearColor",
+    "FGuid",
+    "FDateTime",
+    "FTimespan",
+    "TArray",
+    "TMap",
+    "TSet",
+    "TPair",
+    "TTuple",
+    "TOptional",
+    "TFunction",
+    "TSharedPtr",
+    "TSharedRef",
+    "TWeakPtr",
+    "TUniquePtr",
+    "TObjectPtr",
+    "TWeakObjectPtr",
+    "TSoftObjectPtr",
+    "TSoftClassPtr",
+    "TLazyObjectPtr",
+    "TSubclassOf",
+    "TScriptInterface",
+];
+
+/// Wrappers that only hold a pointer, so the types inside them can be forward declared.
+const POINTER_WRAPPERS: &[&str] = &[
+    "TObjectPtr",
+    "TWeakObjectPtr",
+    "TSoftObjectPtr",
+    "TSoftClassPtr",
+    "TLazyObjectPtr",
+    "TSubclassOf",
+    "TScriptInterface",
+    "TSharedPtr",
+    "TSharedRef",
+    "TWeakPtr",
+    "TUniquePtr",
+];
+
+const BUILTIN_TYPES: &[&str] = &[
+    "void", "bool", "char", "short", "int", "long", "float", "double", "signed", "unsigned",
+    "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "TCHAR", "const",
+    "volatile", "struct", "class", "enum", "auto", "string",
+];
+
+/// How a header uses a type: through a pointer or reference, where a forward declaration is enough,
+/// or by value, where the full definition has to be included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Usage {
+    Indirect,
+    Direct,
+}
+
+/// A type as it's spelled in C++, e.g. `const TArray<AActor*>&`.
+#[derive(Debug)]
+struct TypeNode {
+    name: String,
+    args: Vec<TypeNode>,
+    indirect: bool,
+}
+
+fn tokenize(spelling: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = spelling.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            let mut word = c.to_string();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == ':') {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(word);
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+/// Reads one type starting at `tokens[*at]`; anything it can't make sense of ends the type early.
+fn parse(tokens: &[String], at: &mut usize) -> Option<TypeNode> {
+    while tokens.get(*at).is_some_and(|t| {
+        matches!(
+            t.as_str(),
+            "const" | "volatile" | "struct" | "class" | "enum"
+        )
+    }) {
+        *at += 1;
+    }
+
+    let name = tokens.get(*at)?.clone();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        return None;
+    }
+    *at += 1;
+
+    let mut args = vec![];
+    if tokens.get(*at).is_some_and(|t| t == "<") {
+        *at += 1;
+        while let Some(arg) = parse(tokens, at) {
+            args.push(arg);
+            if tokens.get(*at).is_some_and(|t| t == ",") {
+                *at += 1;
+            } else {
+                break;
+            }
+        }
+        if tokens.get(*at).is_some_and(|t| t == ">") {
+            *at += 1;
+        }
+    }
+
+    let mut indirect = false;
+    while let Some(token) = tokens.get(*at) {
+        match token.as_str() {
+            "*" | "&" => indirect = true,
+            "const" | "[" | "]" => {}
+            _ => break,
+        }
+        *at += 1;
+    }
+
+    Some(TypeNode {
+        name,
+        args,
+        indirect,
+    })
+}
+
+fn collect(node: &TypeNode, indirect: bool, used: &mut BTreeMap<String, Usage>) {
+    let indirect = indirect || node.indirect;
+
+    // templates themselves can't be forward declared, only what they're instantiated with
+    let usage = if indirect && node.args.is_empty() {
+        Usage::Indirect
+    } else {
+        Usage::Direct
+    };
+    let entry = used.entry(node.name.clone()).or_insert(usage);
+    *entry = (*entry).max(usage);
+
+    let args_indirect = indirect || POINTER_WRAPPERS.contains(&node.name.as_str());
+    for arg in &node.args {
+        collect(arg, args_indirect, used);
+    }
+}
+
+fn collect_spelling(spelling: &str, used: &mut BTreeMap<String, Usage>) {
+    let tokens = tokenize(spelling);
+    let mut at = 0;
+    if let Some(node) = parse(&tokens, &mut at) {
+        collect(&node, false, used);
+    }
+}
+
+/// What a generated header has to include, and what it can get away with forward declaring.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct References {
+    /// Header paths, e.g. `GameFramework/Pawn.h`.
+    pub includes: Vec<String>,
+    /// Whole declarations, e.g. `class APawn;`.
+    pub forward_declarations: Vec<String>,
+    /// Headers of the forward declared types, for the .cpp, which usually needs the whole type.
+    pub source_includes: Vec<String>,
+    /// Types used by value that have no known header, so they can't be included or forward declared.
+    pub unknown: Vec<String>,
+}
+
+/// The header for a type: the project's `[headers]` map first, then the other .moho files, then the engine
+/// class table. Anything else has no header moho knows of, so none is made up for it.
+pub fn header_for(
+    name: &str,
+    headers: &BTreeMap<String, String>,
+    symbols: &SymbolTable,
+) -> Option<String> {
+    if let Some(header) = headers.get(name) {
+        return Some(header.clone());
+    }
+    if symbols.defined_in(name).is_some() {
+        return Some(format!("{}.h", name));
+    }
+    engine_header(name).map(|header| header.to_string())
+}
+
+/// A forward declaration has to say `struct` for a struct; the project's and the engine's structs are known,
+/// anything else is declared as a `class`.
+fn declaration_keyword(name: &str, symbols: &SymbolTable) -> &'static str {
+    if symbols.has_struct(name) || is_engine_struct(name) {
+        "struct"
+    } else {
+        "class"
+    }
+}
+
+/// The types `name` uses in its parents, fields, and method signatures, split into includes and forward declarations.
+pub fn references(
+    name: &str,
+    inherit: &[String],
+    inner: &Block,
+    headers: &BTreeMap<String, String>,
+    symbols: &SymbolTable,
+) -> References {
+    let mut used = BTreeMap::new();
+    for parent in inherit {
+        used.insert(parent.clone(), Usage::Direct);
+    }
+    for mut field in inner.clone().fields() {
+        collect_spelling(&field.typ.cpp(), &mut used);
+    }
+    for method in inner.clone().methods() {
+        collect_spelling(&method.returns, &mut used);
+        for argument in &method.arguments {
+            collect_spelling(&argument.typ, &mut used);
+        }
+    }
+    used.remove(name);
+
+    let mut includes = BTreeSet::new();
+    let mut forward_declarations = BTreeSet::new();
+    let mut source_includes = BTreeSet::new();
+    let mut unknown = BTreeSet::new();
+    for (typ, usage) in used {
+        if BUILTIN_TYPES.contains(&typ.as_str()) || CORE_TYPES.contains(&typ.as_str()) {
+            continue;
+        }
+
+        // an enum can't be forward declared without knowing its underlying type
+        if usage == Usage::Indirect && !symbols.has_enum(&typ) && !typ.contains("::") {
+            let keyword = declaration_keyword(&typ, symbols);
+            forward_declarations.insert(format!("{} {};", keyword, typ));
+            source_includes.extend(header_for(&typ, headers, symbols));
+        } else if let Some(header) = header_for(&typ, headers, symbols) {
+            includes.insert(header);
+        } else {
+            unknown.insert(typ);
+        }
+    }
+
+    References {
+        includes: includes.into_iter().collect(),
+        forward_declarations: forward_declarations.into_iter().collect(),
+        source_includes: source_includes.into_iter().collect(),
+        unknown: unknown.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod includes_tests {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use crate::{
+        includes::references,
+        parser::{MohoParser, TranslationUnit},
+        symbols::SymbolTable,
+    };
+
+    /// The project's symbols, as if the unit's records were defined in `project.moho`.
+    fn symbols_of(unit: &TranslationUnit) -> SymbolTable {
+        let mut symbols = SymbolTable::default();
+        for structure in &unit.structs {
+            symbols.structs.insert(
+                structure.name.clone(),
+                (structure.clone(), PathBuf::from("project.moho")),
+            );
+        }
+        symbols
+    }
+
+    #[test]
+    fn pointers_are_forward_declared_values_are_included() {
+        let (unit, errors) = MohoParser::apply_recovering(
+            r#"
+struct FLoot
+{
+    int32 Gold;
+}
+
+class AGoblin : ACharacter
+{
+    AWeapon* Weapon;
+    FLoot Loot;
+    TArray<UItem*> Items;
+    TSubclassOf<AProjectile> ProjectileClass;
+    UStaticMeshComponent* Mesh;
+    FGameplayTag Tag;
+
+    APawn* FindTarget(const FVector& Origin, ULootTable* Table);
+    void Equip(FLoot Loot);
+}
+"#,
+        );
+        assert!(errors.is_empty());
+        let class = &unit.classes[0];
+
+        let headers = BTreeMap::from([(
+            "FGameplayTag".to_string(),
+            "GameplayTagContainer.h".to_string(),
+        )]);
+        let refs = references(
+            &class.name,
+            &class.inherit,
+            &class.inner,
+            &headers,
+            &symbols_of(&unit),
+        );
+
+        assert_eq!(
+            refs.includes,
+            vec![
+                "FLoot.h",
+                "GameFramework/Character.h",
+                "GameplayTagContainer.h"
+            ]
+        );
+        assert_eq!(
+            refs.forward_declarations,
+            vec![
+                "class APawn;",
+                "class AProjectile;",
+                "class AWeapon;",
+                "class UItem;",
+                "class ULootTable;",
+                "class UStaticMeshComponent;",
+            ]
+        );
+        assert_eq!(
+            refs.source_includes,
+            vec!["Components/StaticMeshComponent.h", "GameFramework/Pawn.h"]
+        );
+        assert!(refs.unknown.is_empty());
+    }
+
+    #[test]
+    fn unknown_types_get_no_made_up_headers() {
+        let (unit, errors) = MohoParser::apply_recovering(
+            r#"
+struct FItem
+{
+    int32 Count;
+}
+
+class AHero : AMysteryBase
+{
+    UTexture2D* Portrait;
+    FStats Stats;
+    FItem* Item;
+    FTableRowBase* Row;
+    FThing* Thing;
+}
+"#,
+        );
+        assert!(errors.is_empty());
+        let class = &unit.classes[0];
+
+        let refs = references(
+            &class.name,
+            &class.inherit,
+            &class.inner,
+            &BTreeMap::new(),
+            &symbols_of(&unit),
+        );
+
+        // neither the unknown parent nor the unknown value type gets a `<Name>.h`
+        assert!(refs.includes.is_empty());
+        assert_eq!(refs.unknown, vec!["AMysteryBase", "FStats"]);
+
+        // the keyword comes from what the type is known to be, not from its prefix
+        assert_eq!(
+            refs.forward_declarations,
+            vec![
+                "class FThing;",
+                "class UTexture2D;",
+                "struct FItem;",
+                "struct FTableRowBase;",
+            ]
+        );
+        assert_eq!(refs.source_includes, vec!["Engine/DataTable.h", "FItem.h"]);
+    }
+}
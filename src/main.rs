@@ -1,39 +1,82 @@
 pub mod defaults;
+pub mod diagnostics;
+pub mod diff;
+pub mod error;
 pub mod grammar;
+pub mod hierarchy;
+pub mod includes;
 pub mod output;
 pub mod parser;
+pub mod regions;
+pub mod settings;
+pub mod symbols;
+pub mod watch;
 
 use std::{
+    collections::BTreeSet,
     ffi::{OsStr, OsString},
     fs,
-    io::{self, Write},
-    path::PathBuf,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
     thread,
 };
 
-use clap::Parser;
-use defaults::copy_default_files;
+use clap::{CommandFactory, ErrorKind, Parser, Subcommand};
+use defaults::{copy_default_files, stale_copies};
+use diagnostics::Diagnostic;
+use diff::{colorize_diff, unified_diff};
 use dirs::config_dir;
-use output::{write_file, OutputTemplate, OutputWriter, StringWriter};
+use error::{ErrorCode, MohoError};
+use hierarchy::{declared_parent, engine_parent, only_declares_parent};
+use includes::{header_for, references, References};
+use output::{Dependencies, OutputSink, OutputTemplate, OutputWriter, StringWriter};
 use parser::{
-    Argument, Block, Class, Declaration, Field, Method, MohoParser, Property, TranslationUnit,
-    Type, Value,
+    Argument, Block, Class, Declaration, Enum, EnumEntry, Field, Method, MohoParser, Property,
+    Struct, TranslationUnit, Type, Value,
 };
+use settings::{Found, Settings};
+use symbols::SymbolTable;
 use walkdir::WalkDir;
 
 #[derive(Parser, Debug)]
 #[clap(version)]
 /// Moho: a gamedev-oriented code generator
 pub struct CmdArguments {
+    #[clap(subcommand)]
+    pub command: Option<MohoCommand>,
+
     #[clap(index = 1)]
     /// directory to run generation in
-    pub run_path: String,
+    pub run_path: Option<String>,
+
+    #[clap(long, short, global = true)]
+    /// user directory to find Moho config in, searched after the project's (defaults to .moho in the config dir)
+    pub moho_path: Option<String>,
+
+    #[clap(long, global = true)]
+    /// project config to use instead of the moho.toml found in or above the run directory
+    pub config: Option<String>,
+
+    #[clap(long, global = true, multiple_occurrences = true)]
+    /// only generate .moho files matching this glob, relative to the project root
+    pub include: Vec<String>,
+
+    #[clap(long, global = true, multiple_occurrences = true)]
+    /// skip .moho files matching this glob, relative to the project root
+    pub exclude: Vec<String>,
 
-    #[clap(long, short, default_value_t = config_dir()
-        .map(|c| format!("{}{}.moho", c.as_os_str().to_str().unwrap().to_string(), std::path::MAIN_SEPARATOR))
-        .unwrap_or(String::from(".")))]
-    /// directory to find Moho config
-    pub moho_path: String,
+    #[clap(long, global = true)]
+    /// write every generated file into this directory, instead of the output mapping from moho.toml
+    pub output_dir: Option<String>,
+
+    #[clap(long, global = true)]
+    /// script used for classes without a superclass
+    pub default_script: Option<String>,
+
+    #[clap(long, global = true)]
+    /// prefix for generated field names
+    pub field_prefix: Option<String>,
 
     #[clap(short, long, action, default_value_t = false)]
     /// open explorer to the moho config folder
@@ -42,6 +85,14 @@ pub struct CmdArguments {
     #[clap(short, long, action, default_value_t = false)]
     pub copy_configs: bool,
 
+    #[clap(long, action, default_value_t = false)]
+    /// list the files that would be created or modified, without writing anything
+    pub dry_run: bool,
+
+    #[clap(long, action, default_value_t = false, requires = "dry-run")]
+    /// used in conjunction with --dry-run, also prints a diff for every file that would change
+    pub diff: bool,
+
     #[clap(short, long)]
     /// generate a class of this superclass from the command line
     pub generate_class: Option<String>,
@@ -51,20 +102,35 @@ pub struct CmdArguments {
     pub new_class_name: Option<String>,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum MohoCommand {
+    /// generate into memory and fail if any file on disk differs, without writing anything
+    Check {
+        /// directory to check
+        run_path: String,
+    },
+    /// keep running and regenerate whenever a .moho file, script, or template changes
+    Watch {
+        /// directory to watch
+        run_path: String,
+    },
+}
+
 impl Default for CmdArguments {
     fn default() -> Self {
         Self {
-            run_path: ".".into(),
+            command: None,
+            dry_run: false,
+            diff: false,
+            run_path: Some(".".into()),
             copy_configs: false,
-            moho_path: config_dir()
-                .map(|c| {
-                    format!(
-                        "{}{}.moho",
-                        c.as_os_str().to_str().unwrap().to_string(),
-                        std::path::MAIN_SEPARATOR
-                    )
-                })
-                .unwrap_or(String::from(".")),
+            moho_path: Some(default_moho_path()),
+            config: None,
+            include: vec![],
+            exclude: vec![],
+            output_dir: None,
+            default_script: None,
+            field_prefix: None,
             explore_configs: false,
             generate_class: None,
             new_class_name: None,
@@ -72,16 +138,26 @@ impl Default for CmdArguments {
     }
 }
 
+/// The user-wide config folder, `.moho` in the platform's config directory.
+pub fn default_moho_path() -> String {
+    config_dir()
+        .map(|c| {
+            format!(
+                "{}{}.moho",
+                c.as_os_str().to_str().unwrap(),
+                std::path::MAIN_SEPARATOR
+            )
+        })
+        .unwrap_or(String::from("."))
+}
+
 fn main() -> std::io::Result<()> {
     let args = CmdArguments::parse();
 
-    // Create moho directory if missing
-    let moho_path = args.moho_path.clone();
-    if std::fs::metadata(moho_path.as_str()).is_err() {
-        let _ = std::fs::create_dir(moho_path.as_str());
-        copy_default_files(&moho_path);
-        println!("Copied default moho files to config path {}", moho_path);
-    }
+    // lets pest remember which literal tokens it expected, so syntax errors can say "expected `;`"
+    pest::set_error_detail(true);
+
+    let moho_path = args.moho_path.clone().unwrap_or(default_moho_path());
 
     if args.copy_configs {
         let _ = std::fs::create_dir(moho_path.as_str());
@@ -89,27 +165,85 @@ fn main() -> std::io::Result<()> {
         println!("Copied default moho files to config path {}", moho_path);
     }
 
-    // If exploring, open explorer and quit.
+    // If exploring, open explorer and quit. The folder isn't made on first run anymore, since the
+    // built-in files are used when it's missing, so it's made here to have something to open.
     if args.explore_configs {
-        open::that(args.moho_path)?;
+        std::fs::create_dir_all(&moho_path)?;
+        open::that(moho_path)?;
+        return Ok(());
+    }
+
+    let run_path = match (&args.command, &args.run_path) {
+        (Some(MohoCommand::Check { run_path }), _)
+        | (Some(MohoCommand::Watch { run_path }), _)
+        | (None, Some(run_path)) => run_path.clone(),
+        (None, None) => CmdArguments::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "a directory to run generation in is required",
+            )
+            .exit(),
+    };
+
+    let settings = match Settings::load(Path::new(&run_path), &args) {
+        Ok(settings) => settings,
+        Err(error) => {
+            eprintln!("{}", Diagnostic::new(error));
+            std::process::exit(1);
+        }
+    };
+
+    warn_stale_copies(&moho_path);
+
+    if let Some(MohoCommand::Watch { .. }) = &args.command {
+        watch::watch(Path::new(&run_path), &settings);
+    }
+
+    if let Some(MohoCommand::Check { .. }) = &args.command {
+        let sink = OutputSink::memory();
+        let failed = generate(&run_path, &settings, &sink);
+
+        let pending = sink.pending();
+        for write in &pending {
+            print!(
+                "{}",
+                unified_diff(
+                    &relative_to_cwd(&write.path),
+                    write.on_disk.as_deref(),
+                    &write.generated
+                )
+            );
+        }
+
+        let stale = pending.len();
+        if failed > 0 {
+            eprintln!("moho failed on {} file(s)", failed);
+        }
+        if stale > 0 {
+            eprintln!("{} generated file(s) are out of date", stale);
+        }
+        if failed > 0 || stale > 0 {
+            std::process::exit(1);
+        }
+
+        println!("All generated files are up to date");
         return Ok(());
     }
 
     if let Some(class) = args.generate_class {
         println!("Creating class as a subclass of {}", class);
-        if std::fs::metadata(format!(
-            "{}{}{}.rhai",
-            &moho_path,
-            std::path::MAIN_SEPARATOR,
-            class
-        ))
-        .is_err()
+        if settings
+            .search_path
+            .find(&format!("{}.rhai", class))
+            .is_none()
         {
             println!("\n\tError: template {} not found. Quitting.", class);
             return Ok(());
         }
 
-        let name = if args.new_class_name.is_none() {
+        let name = if let Some(name) = args.new_class_name {
+            name
+        } else {
             print!("  Class name: ");
             let _ = io::stdout().flush();
             {
@@ -117,51 +251,130 @@ fn main() -> std::io::Result<()> {
                 io::stdin().read_line(&mut name).unwrap();
                 name.trim().to_string()
             }
-        } else {
-            args.new_class_name.unwrap()
         };
 
         print!("Writing {}.moho... ", name);
         let _ = io::stdout().flush();
         let _ = fs::write(
-            format!(
-                "{}{}{}.moho",
-                args.run_path,
-                std::path::MAIN_SEPARATOR,
-                name
-            ),
+            format!("{}{}{}.moho", run_path, std::path::MAIN_SEPARATOR, name),
             format!("class {} : {}\n{{\n\n}}\n", name, class),
         );
         println!("Done!");
         return Ok(());
     }
 
-    // Do default stuff
-    let mut handles = vec![];
-    for entry in WalkDir::new(args.run_path) {
-        let Ok(entry) = entry else {
-            continue;
-        };
+    if args.dry_run {
+        let sink = OutputSink::memory();
+        let failed = generate(&run_path, &settings, &sink);
 
-        if entry
-            .path()
-            .extension()
-            .map(|e| e == "moho")
-            .unwrap_or_default()
-        {
-            let path = entry.path().to_path_buf();
-            let moho_path = args.moho_path.clone();
-            handles.push(thread::spawn(move || run_moho(path, moho_path.into())));
+        let pending = sink.pending();
+        for write in &pending {
+            let path = relative_to_cwd(&write.path);
+            if write.is_new() {
+                println!("would create {}", path.display());
+            } else {
+                println!("would modify {}", path.display());
+            }
+        }
+
+        if args.diff {
+            let color = io::stdout().is_terminal();
+            for write in &pending {
+                let diff = unified_diff(
+                    &relative_to_cwd(&write.path),
+                    write.on_disk.as_deref(),
+                    &write.generated,
+                );
+                print!("{}", if color { colorize_diff(&diff) } else { diff });
+            }
+        }
+
+        if pending.is_empty() {
+            println!("Nothing would change");
+        }
+
+        if failed > 0 {
+            eprintln!("moho failed on {} file(s)", failed);
+            std::process::exit(1);
         }
+
+        return Ok(());
     }
 
-    for handle in handles {
-        let _ = handle.join();
+    // Do default stuff
+    let sink = OutputSink::disk();
+    let failed = generate(&run_path, &settings, &sink);
+    println!("Generated files: {}", sink.summary());
+    if failed > 0 {
+        eprintln!("moho failed on {} file(s)", failed);
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Runs every .moho file under `run_path` on its own thread, reports their errors, and returns how many failed.
+fn generate(run_path: &str, settings: &Settings, sink: &OutputSink) -> usize {
+    let files = WalkDir::new(run_path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| settings.includes(entry.path()))
+        .map(|entry| entry.path().to_path_buf())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    // every file is parsed once up front, so classes can inherit from classes in other files
+    let (symbols, duplicates) = SymbolTable::build(&files);
+    let symbols = Arc::new(symbols);
+
+    // a file redefining a name isn't generated, or both files would race to write the same output
+    let mut failed = duplicates
+        .iter()
+        .filter_map(|error| error.file.clone())
+        .collect::<BTreeSet<_>>();
+    for error in duplicates {
+        eprintln!("{}", Diagnostic::new(error));
+    }
+
+    let mut handles = vec![];
+    for path in files.into_iter().filter(|path| !failed.contains(path)) {
+        let settings = settings.clone();
+        let sink = sink.clone();
+        let symbols = symbols.clone();
+        let file = path.clone();
+        handles.push((
+            file,
+            thread::spawn(move || run_moho(path, settings, sink, Dependencies::default(), symbols)),
+        ));
+    }
+
+    for (file, handle) in handles {
+        match handle.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(errors)) => {
+                for error in errors {
+                    eprintln!("{}", Diagnostic::new(error));
+                }
+                failed.insert(file);
+            }
+            Err(_) => {
+                failed.insert(file);
+            }
+        }
+    }
+
+    failed.len()
+}
+
+/// Generated paths are absolute, so they're shown relative to where moho was run.
+fn relative_to_cwd(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(|p| p.to_path_buf()))
+        .unwrap_or(path.to_path_buf())
+}
+
 fn append_to_path(p: impl Into<OsString>, s: impl AsRef<OsStr>) -> String {
     let mut p = p.into();
     p.push(std::path::MAIN_SEPARATOR_STR);
@@ -173,10 +386,6 @@ fn append_to_path(p: impl Into<OsString>, s: impl AsRef<OsStr>) -> String {
         .expect("Should be able to open path")
 }
 
-fn create_file(p: String) {
-    let _ = fs::File::create(p).expect("Cannot create file, panicking!");
-}
-
 fn join_string_array(v: Vec<String>) -> String {
     v.join(", ")
 }
@@ -185,8 +394,8 @@ fn join_property_array(v: Vec<Property>) -> String {
     join_string_array(
         v.iter()
             .map(|p| {
-                if p.value.is_some() {
-                    format!("{}={}", p.name, p.value.as_ref().unwrap())
+                if let Some(value) = &p.value {
+                    format!("{}={}", p.name, value)
                 } else {
                     p.name.to_string()
                 }
@@ -195,33 +404,297 @@ fn join_property_array(v: Vec<Property>) -> String {
     )
 }
 
+/// The properties without the ones called `name`, for properties moho reads itself, e.g. `Condition`.
+fn properties_except(v: Vec<Property>, name: &str) -> Vec<Property> {
+    v.into_iter().filter(|p| p.name != name).collect()
+}
+
+/// A `///` comment as `/** ... */`, which Unreal shows as the tooltip, or nothing without one.
+fn doc_comment(doc: String, indent: String) -> String {
+    if doc.is_empty() {
+        return String::new();
+    }
+    if !doc.contains('\n') {
+        return format!("{indent}/** {doc} */\n");
+    }
+    let mut text = format!("{indent}/**\n");
+    for line in doc.split('\n') {
+        if line.is_empty() {
+            text.push_str(&format!("{indent} *\n"));
+        } else {
+            text.push_str(&format!("{indent} * {line}\n"));
+        }
+    }
+    text.push_str(&format!("{indent} */\n"));
+    text
+}
+
+/// The inside of `UPROPERTY(...)` or `UFUNCTION(...)`, where `meta=(...)` only shows up with meta properties.
+fn specifier_list(properties: Vec<Property>, meta: Vec<Property>) -> String {
+    let mut parts = Vec::new();
+    if !properties.is_empty() {
+        parts.push(join_property_array(properties));
+    }
+    if !meta.is_empty() {
+        parts.push(format!("meta=({})", join_property_array(meta)));
+    }
+    join_string_array(parts)
+}
+
 fn any_string_in_array(v: Vec<String>) -> bool {
     !v.is_empty()
 }
 
-fn run_moho(path: PathBuf, moho_path: PathBuf) {
-    let Ok(input) = fs::read_to_string(path.clone()) else {
-        return;
-    };
+fn any_property_in_array(v: Vec<Property>) -> bool {
+    !v.is_empty()
+}
+
+fn push_record_constants(
+    scope: &mut rhai::Scope,
+    name: &str,
+    inherit: &[String],
+    inner: &Block,
+    doc: Option<String>,
+) {
+    scope.push_constant("Fields", inner.clone().fields());
+    scope.push_constant("Methods", inner.clone().methods());
+    scope.push_constant("Name", name.to_string());
+    scope.push_constant("Doc", doc.unwrap_or_default());
+    scope.push_constant("ClassProperties", inner.properties.clone());
+    scope.push_constant("Inherit", inherit.first().cloned().unwrap_or_default());
+
+    let mut tail = inherit.to_vec();
+    tail.reverse();
+    tail.pop();
+    tail.reverse();
+    scope.push_constant("OtherInherits", tail);
+}
+
+/// Looks a script up along the search path, recording every candidate so watch mode notices when one appears.
+fn resolve_script(settings: &Settings, dependencies: &Dependencies, name: &str) -> Option<Found> {
+    for candidate in settings.search_path.candidates(name) {
+        dependencies.record(candidate);
+    }
+    settings.search_path.find(name)
+}
+
+/// The script for a class with this superclass: the superclass's own, or else the nearest ancestor's, following
+/// parents declared by scripts and then the engine class table. Classes from .moho files don't pick scripts, so
+/// the first class above them that isn't decides. Also returns every ancestor it found.
+fn resolve_class_script(
+    settings: &Settings,
+    dependencies: &Dependencies,
+    symbols: &SymbolTable,
+    superclass: Option<&str>,
+) -> (String, Option<Found>, Vec<String>) {
+    let mut ancestors: Vec<String> = vec![];
+    let mut chosen = None;
+    let mut current = superclass.map(|s| s.to_string());
+
+    while let Some(class) = current {
+        if ancestors.contains(&class) {
+            break;
+        }
+        ancestors.push(class.clone());
+
+        if let Some(file) = symbols.defined_in(&class) {
+            dependencies.record(file);
+            current = symbols.parent(&class).map(|p| p.to_string());
+            continue;
+        }
+
+        let script_name = class.clone() + ".rhai";
+        let script = resolve_script(settings, dependencies, &script_name);
+        let source = script.as_ref().and_then(|s| s.read().ok());
 
-    let Ok(translation_unit) = MohoParser::apply(&input) else {
-        return;
+        if let (None, Some(script), Some(source)) = (&chosen, &script, &source) {
+            if !only_declares_parent(source) {
+                chosen = Some((script_name, script.clone()));
+            }
+        }
+
+        current = source
+            .as_deref()
+            .and_then(declared_parent)
+            .or(engine_parent(&class).map(|p| p.to_string()));
+    }
+
+    match chosen {
+        Some((script_name, script)) => (script_name, Some(script), ancestors),
+        None => {
+            if let Some(superclass) = superclass {
+                eprintln!(
+                    "warning: no script for `{}` or any class it inherits from, using {}",
+                    superclass, settings.default_script
+                );
+            }
+
+            let script_name = settings.default_script.clone();
+            let script = resolve_script(settings, dependencies, &script_name);
+            (script_name, script, ancestors)
+        }
+    }
+}
+
+/// Copies of built-in files from an older moho hide the new versions, so what's generated can quietly differ
+/// from what the README describes.
+fn warn_stale_copies(moho_path: &str) {
+    for copy in stale_copies(moho_path) {
+        if copy.edited {
+            eprintln!(
+                "warning: `{}` in {} was copied from an older built-in version and hides the current one; merge your changes into a fresh copy from `moho -c`",
+                copy.name, moho_path
+            );
+        } else {
+            eprintln!(
+                "warning: `{}` in {} is an unchanged copy of an older built-in version and hides the current one; delete it",
+                copy.name, moho_path
+            );
+        }
+    }
+}
+
+/// Types used by value need their whole definition, so one without a known header breaks the build.
+fn warn_unknown_types(name: &str, refs: &References) {
+    for typ in &refs.unknown {
+        eprintln!(
+            "warning: no header known for `{}`, used in `{}`; add it to `[headers]` in moho.toml",
+            typ, name
+        );
+    }
+}
+
+fn run_script(
+    engine: &rhai::Engine,
+    scope: &mut rhai::Scope,
+    script_name: &str,
+    script: Option<Found>,
+    subject: &str,
+    moho_file: &Path,
+) -> Result<(), MohoError> {
+    let Some(script) = script else {
+        return Err(MohoError::new(
+            ErrorCode::Script,
+            format!(
+                "while generating {}: `{}` isn't in any script folder and isn't built in",
+                subject, script_name
+            ),
+        )
+        .in_file(moho_file));
     };
 
-    let Some(source_dir) = path.clone().parent().map(|p| p.to_path_buf()) else {
-        return;
+    let path = script.path();
+    let source = script.read().map_err(|e| {
+        MohoError::new(ErrorCode::Io, format!("cannot read file: {}", e)).in_file(&path)
+    })?;
+
+    engine
+        .run_with_scope(scope, &source)
+        .map_err(|e| MohoError::script(&path, &source, subject, &e))
+}
+
+/// Net functions run on another machine, so one returning anything but `void` is an error.
+/// A net function that doesn't return `void` can't be generated; the rest of the class still is.
+fn is_broken_net_function(method: &Method) -> bool {
+    method.clone().is_net() && method.returns.trim() != "void"
+}
+
+/// The block without its broken net functions, at any depth; `upper` are the properties of the blocks around it.
+fn without_broken_net_functions(block: &Block, upper: &[Property]) -> Block {
+    let mut properties = upper.to_vec();
+    properties.extend(block.properties.clone());
+
+    let is_kept = |decl: &&Declaration| match decl {
+        Declaration::Method(method) => {
+            let mut method = method.clone();
+            method.properties = [properties.clone(), method.properties].concat();
+            !is_broken_net_function(&method)
+        }
+        _ => true,
     };
 
+    Block {
+        properties: block.properties.clone(),
+        inner: block
+            .inner
+            .iter()
+            .filter(is_kept)
+            .map(|decl| match decl {
+                Declaration::Block(inner) => {
+                    Declaration::Block(without_broken_net_functions(inner, &properties))
+                }
+                decl => decl.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn net_function_errors(inner: &Block) -> Vec<MohoError> {
+    inner
+        .clone()
+        .methods()
+        .into_iter()
+        .filter(is_broken_net_function)
+        .map(|method| {
+            let error = MohoError::new(
+                ErrorCode::InvalidMethod,
+                format!(
+                    "net function `{}` returns `{}`, but has to return `void`",
+                    method.name, method.returns
+                ),
+            );
+            match method.location {
+                Some(location) => error.at(location),
+                None => error,
+            }
+        })
+        .collect()
+}
+
+fn run_moho(
+    path: PathBuf,
+    settings: Settings,
+    sink: OutputSink,
+    dependencies: Dependencies,
+    symbols: Arc<SymbolTable>,
+) -> Result<(), Vec<MohoError>> {
+    let input = fs::read_to_string(path.clone()).map_err(|e| {
+        vec![MohoError::new(ErrorCode::Io, format!("cannot read file: {}", e)).in_file(&path)]
+    })?;
+
+    // everything that parsed still gets generated, but the file counts as failed
+    let (translation_unit, errors) = MohoParser::apply_recovering(&input);
+    let mut errors = errors
+        .into_iter()
+        .map(|e| e.in_file(&path))
+        .collect::<Vec<_>>();
+
+    let source_dir = path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or(PathBuf::from("."));
+
     let mut engine = rhai::Engine::new();
-    engine.register_fn("create_file", create_file);
-    engine.register_fn("write_file", write_file);
+    let file_sink = sink.clone();
+    engine.register_fn("create_file", move |p: String| {
+        file_sink.write(p, String::new())
+    });
+    let file_sink = sink.clone();
+    engine.register_fn("write_file", move |p: String, content: String| {
+        file_sink.write(p, content)
+    });
     engine.register_fn("join", join_string_array);
     engine.register_fn("join", join_property_array);
+    engine.register_fn("except", properties_except);
     engine.register_fn("any", any_string_in_array);
+    engine.register_fn("any", any_property_in_array);
+    engine.register_fn("doc_comment", doc_comment);
+    engine.register_fn("specifier_list", specifier_list);
     engine.build_type::<OutputWriter>();
     engine.build_type::<StringWriter>();
     engine.build_type::<OutputTemplate>();
     engine.build_type::<Class>();
+    engine.build_type::<Struct>();
     engine.build_type::<Block>();
     engine.build_type::<Property>();
     engine.build_type::<Field>();
@@ -229,74 +702,670 @@ fn run_moho(path: PathBuf, moho_path: PathBuf) {
     engine.build_type::<Value>();
     engine.build_type::<Method>();
     engine.build_type::<Argument>();
+    engine.build_type::<Enum>();
+    engine.build_type::<EnumEntry>();
+    engine.build_type::<SymbolTable>();
 
     engine.register_iterator::<Vec<String>>();
     engine.register_iterator::<Vec<Block>>();
+    engine.register_iterator::<Vec<Type>>();
     engine.register_iterator::<Vec<Field>>();
     engine.register_iterator::<Vec<Method>>();
     engine.register_iterator::<Vec<Argument>>();
     engine.register_iterator::<Vec<Property>>();
     engine.register_iterator::<Vec<Declaration>>();
+    engine.register_iterator::<Vec<EnumEntry>>();
 
     engine.register_type_with_name::<Declaration>("Declaration");
     engine.register_type_with_name::<TranslationUnit>("TranslationUnit");
-    let mut scope = rhai::Scope::new();
-
-    scope.push(
-        "Output",
-        OutputWriter::new(source_dir.clone(), moho_path.clone()),
-    );
-    scope.push_constant(
-        "Filename",
-        path.clone().file_name().map(|f| f.to_owned()).unwrap(),
-    );
-    scope.push_constant(
-        "Path",
-        source_dir
-            .canonicalize()
-            .unwrap()
-            .as_os_str()
-            .to_os_string()
-            .into_string()
-            .unwrap(),
-    );
-
-    for class in translation_unit.0 {
+    // every record gets a scope of its own, with a fresh Output, so nothing a script leaves behind for one
+    // record ends up in the next one's files
+    let file_scope = || {
+        let mut scope = rhai::Scope::new();
+        scope.push(
+            "Output",
+            OutputWriter::new(
+                settings.output_dir(&source_dir),
+                settings.search_path.clone(),
+                sink.clone(),
+                dependencies.clone(),
+            ),
+        );
+        scope.push_constant(
+            "Filename",
+            path.clone().file_name().map(|f| f.to_owned()).unwrap(),
+        );
+        scope.push_constant(
+            "Path",
+            source_dir
+                .canonicalize()
+                .unwrap()
+                .as_os_str()
+                .to_os_string()
+                .into_string()
+                .unwrap(),
+        );
+        scope.push_constant("FieldPrefix", settings.field_prefix.clone());
+        scope.push_constant("Project", symbols.as_ref().clone());
+        scope
+    };
+
+    for class in translation_unit.classes {
         let Class {
             name,
             inherit,
             inner,
+            doc,
         } = class.clone();
+        let mut scope = file_scope();
+
+        // a broken net function is reported and left out, so classes that extend this one still have a parent
+        let net_errors = net_function_errors(&inner);
+        errors.extend(net_errors.into_iter().map(|e| e.in_file(&path)));
+        let inner = without_broken_net_functions(&inner, &[]);
+
+        push_record_constants(&mut scope, &name, &inherit, &inner, doc);
+        let refs = references(&name, &inherit, &inner, &settings.headers, &symbols);
+
+        let superclass = inherit.first().map(|f| f.as_str());
+        let (script_name, script, ancestors) =
+            resolve_class_script(&settings, &dependencies, &symbols, superclass);
+
+        // a parent without a known header is warned about with the other unknown types, and not included
+        let parent_include = superclass
+            .and_then(|parent| header_for(parent, &settings.headers, &symbols))
+            .unwrap_or_default();
+        warn_unknown_types(&name, &refs);
+        scope.push_constant("Ancestors", ancestors);
+        scope.push_constant("InheritedFields", symbols.inherited_fields(&name));
+        // the header template includes the parent on its own
+        let includes = refs
+            .includes
+            .into_iter()
+            .filter(|include| *include != parent_include)
+            .collect::<Vec<_>>();
+        scope.push_constant("Includes", includes);
+        scope.push_constant("ForwardDeclarations", refs.forward_declarations);
+        scope.push_constant("SourceIncludes", refs.source_includes);
+        scope.push_constant("ParentInclude", parent_include);
+
+        // a script error is reported with the rest, and the next record still gets generated
+        errors.extend(run_script(&engine, &mut scope, &script_name, script, &name, &path).err());
+    }
+
+    for structure in translation_unit.structs {
+        let Struct {
+            name,
+            inherit,
+            inner,
+            doc,
+        } = structure.clone();
+        let mut scope = file_scope();
+
+        push_record_constants(&mut scope, &name, &inherit, &inner, doc);
+        scope.push_constant("InheritedFields", symbols.inherited_fields(&name));
+
+        let refs = references(&name, &inherit, &inner, &settings.headers, &symbols);
+        warn_unknown_types(&name, &refs);
+        scope.push_constant("Includes", refs.includes);
+        scope.push_constant("ForwardDeclarations", refs.forward_declarations);
+        scope.push_constant("SourceIncludes", refs.source_includes);
+
+        // structs with a parent that has its own script (e.g. FTableRowBase) use it
+        let (script_name, script) = inherit
+            .first()
+            .map(|f| f.clone() + ".rhai")
+            .and_then(|script_name| {
+                let script = resolve_script(&settings, &dependencies, &script_name)?;
+                Some((script_name, Some(script)))
+            })
+            .unwrap_or_else(|| {
+                let script_name = String::from("USTRUCT.rhai");
+                let script = resolve_script(&settings, &dependencies, &script_name);
+                (script_name, script)
+            });
+        errors.extend(run_script(&engine, &mut scope, &script_name, script, &name, &path).err());
+    }
+
+    for enumeration in translation_unit.enums {
+        let Enum {
+            name,
+            underlying,
+            properties,
+            entries,
+            doc,
+        } = enumeration.clone();
+        let mut scope = file_scope();
 
-        scope.push_constant("Fields", inner.clone().fields());
-        scope.push_constant("Methods", inner.clone().methods());
         scope.push_constant("Name", name.clone());
-        scope.push_constant("ClassProperties", inner.properties.clone());
-        scope.push_constant("Inherit", inherit.first().cloned());
-
-        let mut tail = inherit.clone();
-        tail.reverse();
-        tail.pop();
-        tail.reverse();
-        scope.push_constant("OtherInherits", tail);
-
-        let script_file = append_to_path(
-            moho_path.clone(),
-            inherit
-                .first()
-                .cloned()
-                .map(|f| f + ".rhai")
-                .unwrap_or("base.rhai".to_string()),
+        scope.push_constant("Doc", doc.unwrap_or_default());
+        scope.push_constant("Underlying", underlying.unwrap_or_default());
+        scope.push_constant("EnumProperties", properties);
+        scope.push_constant("Entries", entries);
+
+        let script = resolve_script(&settings, &dependencies, "UENUM.rhai");
+        errors.extend(run_script(&engine, &mut scope, "UENUM.rhai", script, &name, &path).err());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod generation_tests {
+    use std::{collections::BTreeMap, fs};
+
+    use crate::{generate, output::OutputSink, settings::Settings, CmdArguments};
+
+    /// Runs the built-in scripts on one `.moho` file, and returns the generated files by name.
+    fn generate_files(test: &str, source: &str) -> BTreeMap<String, String> {
+        let (files, failed) = generate_with_failures(test, source);
+        assert_eq!(failed, 0);
+        files
+    }
+
+    /// Like `generate_files`, for a file with errors, plus how many files failed.
+    fn generate_with_failures(test: &str, source: &str) -> (BTreeMap<String, String>, usize) {
+        let root =
+            std::env::temp_dir().join(format!("moho-generation-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("fixture.moho"), source).unwrap();
+
+        // a user config folder that doesn't exist, so only the built-in files are used
+        let args = CmdArguments {
+            moho_path: Some(root.join("User").to_str().unwrap().into()),
+            ..Default::default()
+        };
+        let settings = Settings::load(&root, &args).unwrap();
+        let sink = OutputSink::memory();
+        let failed = generate(root.to_str().unwrap(), &settings, &sink);
+        fs::remove_dir_all(&root).unwrap();
+
+        let files = sink
+            .recorded()
+            .into_iter()
+            .map(|(path, content)| {
+                (
+                    path.file_name().unwrap().to_str().unwrap().to_string(),
+                    content,
+                )
+            })
+            .collect();
+        (files, failed)
+    }
+
+    /// The generated `(.h, .cpp)` of a class.
+    fn generate_class(test: &str, class: &str, source: &str) -> (String, String) {
+        let mut files = generate_files(test, source);
+        let mut file = |extension: &str| {
+            files
+                .remove(&format!("{}.{}", class, extension))
+                .unwrap_or_else(|| panic!("{}.{} wasn't generated", class, extension))
+        };
+        (file("h"), file("cpp"))
+    }
+
+    #[test]
+    fn accessors() {
+        let (header, _) = generate_class(
+            "accessors",
+            "AHero",
+            r#"
+class AHero : AActor
+{
+    [EditAnywhere, Category="Stats", #BlueprintAccessors]
+    float Speed;
+
+    FString Title;
+
+    [#BlueprintAccessors]
+    APawn* Target;
+
+    [BlueprintAssignable, #NoGetter, #NoSetter]
+    FOnDeath OnDeath;
+
+    [#NoSetter]
+    int32 Level;
+
+    [#NoGetter]
+    bool bDead;
+
+    static int32 Count;
+}
+"#,
         );
 
-        if let Err(result) = engine.run_file_with_scope(&mut scope, PathBuf::from(script_file)) {
-            println!(
-                "Failed to execute moho on file {}: {:?}",
-                name.clone(),
-                result
-            );
+        assert!(header.contains("\tUFUNCTION(BlueprintPure, Category=\"Stats\")\n\tfloat GetSpeed() const { return m_Speed; }"));
+        assert!(header.contains(
+            "\tUFUNCTION(BlueprintCallable, Category=\"Stats\")\n\tvoid SetSpeed(float Value)"
+        ));
+        assert!(header.contains("\tconst FString& GetTitle() const { return m_Title; }"));
+        assert!(header.contains("\tvoid SetTitle(const FString& Value)"));
+        assert!(header.contains(
+            "\tUFUNCTION(BlueprintPure)\n\tAPawn* GetTarget() const { return m_Target; }"
+        ));
+        assert!(header.contains("\tUFUNCTION(BlueprintCallable)\n\tvoid SetTarget(APawn* Value)"));
+        assert!(!header.contains("GetOnDeath"));
+        assert!(!header.contains("SetOnDeath"));
+        assert!(header.contains("\tint32 GetLevel() const { return m_Level; }"));
+        assert!(!header.contains("SetLevel"));
+        assert!(!header.contains("GetbDead"));
+        assert!(header.contains("\tvoid SetbDead(bool Value)"));
+        assert!(!header.contains("GetCount"));
+        assert!(!header.contains("NoGetter"));
+        assert!(!header.contains("BlueprintAccessors"));
+    }
 
-            return;
-        }
+    #[test]
+    fn clamp_and_notify() {
+        let (header, _) = generate_class(
+            "clamp",
+            "AHero",
+            r#"
+class AHero : AActor
+{
+    [EditAnywhere, #ClampMin=0, #ClampMax=100, #Notify=OnHealthChanged]
+    float Health;
+
+    [#ClampMin=1, #Notify=OnArmorChanged]
+    int32 Armor;
+
+    [BlueprintAssignable, #NoGetter, #NoSetter]
+    FOnHealthChanged OnHealthChanged;
+}
+"#,
+        );
+
+        assert!(header.contains(
+            "    UPROPERTY(EditAnywhere, meta=(ClampMin=0, ClampMax=100))\n    float m_Health;"
+        ));
+        assert!(header.contains("\t\tm_Health = FMath::Clamp<float>(Value, 0, 100);\n\t\tm_OnHealthChanged.Broadcast(m_Health);"));
+        assert!(header.contains(
+            "\t\tm_Armor = FMath::Max<int32>(Value, 1);\n\t\tOnArmorChanged.Broadcast(m_Armor);"
+        ));
+        assert!(!header.contains("Notify"));
+    }
+
+    #[test]
+    fn replication() {
+        let (header, source) = generate_class(
+            "replication",
+            "AWeaponPickup",
+            r#"
+class AWeaponPickup : AActor
+{
+    [Replicated]
+    int32 Ammo;
+
+    [ReplicatedUsing=OnRep_Owner, Condition=COND_OwnerOnly]
+    APawn* Owner;
+}
+"#,
+        );
+
+        assert!(header.contains("\tvirtual void GetLifetimeReplicatedProps(TArray<FLifetimeProperty>& OutLifetimeProps) const override;"));
+        assert!(header.contains("\tUFUNCTION()\n\tvoid OnRep_Owner();"));
+        assert!(header.contains("    UPROPERTY(Replicated)\n    int32 m_Ammo;"));
+        assert!(header.contains(
+            "    UPROPERTY(ReplicatedUsing=OnRep_Owner)\n    TObjectPtr<APawn> m_Owner;"
+        ));
+        assert!(source.contains("#include \"Net/UnrealNetwork.h\""));
+        assert!(source.contains("    bReplicates = true;"));
+        assert!(source.contains("    DOREPLIFETIME(AWeaponPickup, m_Ammo);"));
+        assert!(
+            source.contains("    DOREPLIFETIME_CONDITION(AWeaponPickup, m_Owner, COND_OwnerOnly);")
+        );
+        assert!(source.contains("void AWeaponPickup::OnRep_Owner()\n{\n    // moho:begin user OnRep_Owner\n    // moho:end\n}"));
+    }
+
+    #[test]
+    fn rpc_stubs() {
+        let (header, source) = generate_class(
+            "rpc",
+            "AWeapon",
+            r#"
+class AWeapon : AActor
+{
+    [Server, Reliable, WithValidation]
+    void Fire(FVector Target);
+
+    [NetMulticast, Unreliable]
+    void PlayFireEffects();
+}
+"#,
+        );
+
+        assert!(header.contains(
+            "    UFUNCTION(Server, Reliable, WithValidation)\n    void Fire(FVector Target);"
+        ));
+        assert!(source.contains("void AWeapon::Fire_Implementation(FVector Target)\n{\n    // moho:begin user Fire_Implementation\n    // moho:end\n}"));
+        assert!(source.contains("bool AWeapon::Fire_Validate(FVector Target)\n{\n    // moho:begin user Fire_Validate\n    return true;\n    // moho:end\n}"));
+        assert!(source.contains("void AWeapon::PlayFireEffects_Implementation()"));
+        assert!(!source.contains("PlayFireEffects_Validate"));
+        assert!(!source.contains("void AWeapon::Fire("));
+    }
+
+    #[test]
+    fn broken_net_function_leaves_the_rest_of_the_class() {
+        let (files, failed) = generate_with_failures(
+            "broken-rpc",
+            r#"
+class AWeapon : AActor
+{
+    [Server, Reliable]
+    int32 Fire();
+
+    [Client, Reliable]
+    void Reload();
+}
+
+class ARifle : AWeapon
+{
+    int32 Ammo;
+}
+"#,
+        );
+
+        // the error still fails the file, but both classes are written
+        assert_eq!(failed, 1);
+        let header = &files["AWeapon.h"];
+        let source = &files["AWeapon.cpp"];
+        assert!(header.contains("    void Reload();"));
+        assert!(!header.contains("Fire"));
+        assert!(source.contains("void AWeapon::Reload_Implementation()"));
+        assert!(!source.contains("Fire"));
+        assert!(files["ARifle.h"].contains("#include \"AWeapon.h\""));
+    }
+
+    #[test]
+    fn records_are_generated_apart() {
+        let (files, failed) = generate_with_failures(
+            "apart",
+            r#"
+class AWeapon : AActor
+{
+    int32 Ammo;
+}
+
+class ABroken : AActor
+{
+    [#Notify]
+    int32 Health;
+}
+
+class ARifle : AWeapon
+{
+    float Spread;
+}
+
+enum EFireMode
+{
+    Single,
+    Burst
+}
+"#,
+        );
+
+        // the script error in ABroken doesn't stop the records after it
+        assert_eq!(failed, 1);
+        assert!(!files.contains_key("ABroken.h"));
+        assert!(files.contains_key("EFireMode.h"));
+
+        let rifle = &files["ARifle.cpp"];
+        assert!(rifle.starts_with("#include \"ARifle.h\""));
+        assert!(!rifle.contains("AWeapon"));
+        assert!(!files["ARifle.h"].contains("m_Ammo;"));
+        assert!(!files["AWeapon.cpp"].contains("ARifle"));
+    }
+
+    #[test]
+    fn method_stubs() {
+        let (header, source) = generate_class(
+            "methods",
+            "AHero",
+            r#"
+class AHero : AActor
+{
+    void Jump();
+
+    int32 Heal(int32 Amount, bool bRevive = false);
+
+    float Heal(float Amount);
+
+    static AHero* Spawn();
+
+    FString& Title();
+
+    const TArray<int32>& Scores();
+
+    [BlueprintNativeEvent]
+    void OnHit();
+
+    [BlueprintImplementableEvent]
+    void OnDeath();
+}
+"#,
+        );
+
+        assert!(header.contains("    UFUNCTION()\n    void Jump();"));
+        assert!(header.contains("    static AHero* Spawn();"));
+        assert!(source
+            .contains("void AHero::Jump()\n{\n    // moho:begin user Jump\n    // moho:end\n}"));
+        assert!(source.contains("int32 AHero::Heal(int32 Amount, bool bRevive)\n{\n    // moho:begin user Heal(int32, bool)\n    return {};\n    // moho:end\n}"));
+        assert!(source.contains("float AHero::Heal(float Amount)\n{\n    // moho:begin user Heal(float)\n    return {};"));
+        assert!(source.contains("AHero* AHero::Spawn()\n{"));
+        assert!(source.contains("FString& AHero::Title()\n{\n    // moho:begin user Title\n    static_assert(false, \"Title returns a reference, return one to something that outlives the call\");\n    // moho:end\n}"));
+        assert!(source.contains("const TArray<int32>& AHero::Scores()\n{\n    // moho:begin user Scores\n    static_assert(false, "));
+        assert!(!source.contains("Placeholder"));
+        assert!(source.contains("void AHero::OnHit_Implementation()"));
+        assert!(!source.contains("OnDeath"));
+    }
+
+    #[test]
+    fn constructor_init() {
+        let (header, source) = generate_class(
+            "constructor",
+            "ATurret",
+            r#"
+class ATurret : AActor
+{
+    USceneComponent* Base;
+
+    [VisibleAnywhere, AttachTo=Base]
+    UStaticMeshComponent* Barrel;
+
+    [AttachTo=RootComponent]
+    UAudioComponent* Speaker;
+
+    [#InitInConstructor, EditAnywhere]
+    float MaxHealth = 100;
+
+    [#InitInConstructor]
+    FVector Offset;
+}
+"#,
+        );
+
+        assert!(header.contains(
+            "    UPROPERTY(VisibleAnywhere)\n    TObjectPtr<UStaticMeshComponent> m_Barrel;"
+        ));
+        assert!(header.contains("    UPROPERTY(EditAnywhere)\n    float m_MaxHealth;"));
+        assert!(source
+            .contains("    m_Base = CreateDefaultSubobject<USceneComponent>(TEXT(\"Base\"));"));
+        assert!(source.contains(
+            "    m_Barrel = CreateDefaultSubobject<UStaticMeshComponent>(TEXT(\"Barrel\"));"
+        ));
+        assert!(source.contains("    m_Barrel->SetupAttachment(m_Base);"));
+        assert!(source.contains("    m_Speaker->SetupAttachment(RootComponent);"));
+        assert!(source.contains("    m_MaxHealth = 100;"));
+        assert!(source.contains("    m_Offset = {};"));
+    }
+
+    #[test]
+    fn struct_methods() {
+        let files = generate_files(
+            "struct",
+            r#"
+struct FItem
+{
+    [EditAnywhere]
+    float Weight = 1;
+
+    /// The price after discounts
+    int32 Price(float Discount = 0);
+
+    [BlueprintCallable]
+    static FItem Empty();
+
+private:
+    int32 Uses;
+
+    [#Access=protected]
+    void Use();
+}
+"#,
+        );
+
+        let header = &files["FItem.h"];
+        assert!(header.contains("    UPROPERTY(EditAnywhere)\n    float Weight = 1;"));
+        assert!(header.contains(
+            "    /** The price after discounts */\n    int32 Price(float Discount = 0);"
+        ));
+        assert!(header.contains("    static FItem Empty();"));
+        assert!(!header.contains("UFUNCTION"));
+        assert!(header.contains("public:\n    UPROPERTY(EditAnywhere)\n    float Weight = 1;"));
+        assert!(header.contains("protected:\n    void Use();"));
+        assert!(header.contains("private:\n    UPROPERTY()\n    int32 Uses;"));
+
+        let source = &files["FItem.cpp"];
+        assert!(source.starts_with("#include \"FItem.h\""));
+        assert!(source.contains(
+            "int32 FItem::Price(float Discount)\n{\n    // moho:begin user Price\n    return {};"
+        ));
+        assert!(source.contains("FItem FItem::Empty()\n{"));
+        assert!(source.contains("void FItem::Use()\n{"));
+
+        // a struct without methods has nothing to define
+        let files = generate_files("struct-fields", "struct FLoot\n{\n    int32 Gold;\n}\n");
+        assert!(files["FLoot.h"].contains("public:\n    UPROPERTY()\n    int32 Gold;"));
+        assert!(!files.contains_key("FLoot.cpp"));
+    }
+
+    #[test]
+    fn unknown_types_are_not_included() {
+        let (header, source) = generate_class(
+            "unknown",
+            "AHero",
+            r#"
+class AHero : AActor
+{
+    UTexture2D* Portrait;
+}
+"#,
+        );
+
+        assert!(header.contains("#include \"GameFramework/Actor.h\"\n\nclass UTexture2D;"));
+        assert!(!source.contains("UTexture2D.h"));
+    }
+
+    #[test]
+    fn types_are_spelled_like_the_type_templates() {
+        let (header, _) = generate_class(
+            "types",
+            "AMap",
+            r#"
+class AMap : AActor
+{
+    string Motto = "hi";
+    int32[][] Grid;
+    TArray<AActor*> Visitors;
+}
+"#,
+        );
+
+        assert!(header.contains("    FString m_Motto = TEXT(\"hi\");"));
+        assert!(header.contains("\tconst FString& GetMotto() const { return m_Motto; }"));
+        assert!(header.contains("    TArray<TArray<int32>> m_Grid;"));
+        assert!(header.contains("    TArray<TObjectPtr<AActor>> m_Visitors;"));
+        assert!(!header.contains("std::string"));
+    }
+
+    #[test]
+    fn project_and_user_files_override_built_ins() {
+        let root =
+            std::env::temp_dir().join(format!("moho-generation-lookup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".moho")).unwrap();
+        fs::create_dir_all(root.join("User")).unwrap();
+        fs::write(
+            root.join("fixture.moho"),
+            "class AHero : AActor\n{\n    string Title;\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join(".moho/field-name-template.txt"), "p{{name}}").unwrap();
+        fs::write(root.join("User/field-name-template.txt"), "u{{name}}").unwrap();
+        fs::write(root.join("User/type-template-string.txt"), "FText").unwrap();
+
+        let args = CmdArguments {
+            moho_path: Some(root.join("User").to_str().unwrap().into()),
+            ..Default::default()
+        };
+        let settings = Settings::load(&root, &args).unwrap();
+        let sink = OutputSink::memory();
+        assert_eq!(generate(root.to_str().unwrap(), &settings, &sink), 0);
+        fs::remove_dir_all(&root).unwrap();
+
+        let header = sink
+            .recorded()
+            .into_iter()
+            .find(|(path, _)| path.ends_with("AHero.h"))
+            .map(|(_, content)| content)
+            .unwrap();
+        // the project's name template, the user's string type, and the built-in everything else
+        assert!(header.contains("    FText pTitle;"));
+        assert!(header.contains("\tconst FText& GetTitle() const { return pTitle; }"));
+        assert!(header.contains("GENERATED_BODY()"));
+    }
+
+    #[test]
+    fn script_writes_are_recorded_in_a_dry_run() {
+        let root = std::env::temp_dir().join(format!("moho-generation-dry-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".moho")).unwrap();
+        fs::write(root.join("fixture.moho"), "class UThing : UObject\n{\n}\n").unwrap();
+        fs::write(
+            root.join(".moho/base.rhai"),
+            "write_file(Path + \"/notes.txt\", Name);\ncreate_file(Path + \"/empty.txt\");\n",
+        )
+        .unwrap();
+
+        let args = CmdArguments {
+            moho_path: Some(root.join("User").to_str().unwrap().into()),
+            ..Default::default()
+        };
+        let settings = Settings::load(&root, &args).unwrap();
+        let sink = OutputSink::memory();
+        assert_eq!(generate(root.to_str().unwrap(), &settings, &sink), 0);
+
+        let root = root.canonicalize().unwrap();
+        assert!(!root.join("notes.txt").exists());
+        assert!(!root.join("empty.txt").exists());
+        let recorded = sink
+            .recorded()
+            .into_iter()
+            .map(|(path, content)| (path.strip_prefix(&root).unwrap().to_path_buf(), content))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recorded,
+            vec![
+                ("empty.txt".into(), String::new()),
+                ("notes.txt".into(), "UThing".into())
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -1,10 +1,171 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use rhai::CustomType;
 use string_template::Template;
 
-pub fn write_file(p: String, content: String) {
+use crate::{
+    regions::merge_user_regions,
+    settings::{Found, SearchPath},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStatus {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// Writes the file only if its content changed, so Unreal doesn't see a new timestamp and rebuild everything.
+pub fn write_file(p: String, content: String) -> WriteStatus {
+    let status = match fs::read(&p) {
+        Ok(existing) if existing == content.as_bytes() => return WriteStatus::Unchanged,
+        Ok(_) => WriteStatus::Updated,
+        Err(_) => WriteStatus::Created,
+    };
+
+    if let Some(dir) = Path::new(&p).parent() {
+        let _ = fs::create_dir_all(dir);
+    }
     fs::write(p.clone(), content).unwrap_or_else(|_| panic!("Cannot write file {}", p));
+    status
+}
+
+/// How many files a run created, updated, and left alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl Display for WriteSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} created, {} updated, {} unchanged",
+            self.created, self.updated, self.unchanged
+        )
+    }
+}
+
+/// Where generated files end up: on disk, or kept in memory so they can be compared against disk.
+#[derive(Default, Clone)]
+pub struct OutputSink {
+    recorded: Option<Arc<Mutex<BTreeMap<PathBuf, String>>>>,
+    written: Arc<Mutex<BTreeMap<PathBuf, WriteStatus>>>,
+}
+
+impl OutputSink {
+    pub fn disk() -> Self {
+        OutputSink::default()
+    }
+
+    pub fn memory() -> Self {
+        OutputSink {
+            recorded: Some(Arc::default()),
+            ..Default::default()
+        }
+    }
+
+    pub fn write(&self, p: String, content: String) {
+        match &self.recorded {
+            Some(recorded) => {
+                recorded.lock().unwrap().insert(PathBuf::from(p), content);
+            }
+            None => {
+                let path = PathBuf::from(&p);
+                let status = write_file(p, content);
+
+                // a file written twice in one run counts once, as whatever first changed it
+                let mut written = self.written.lock().unwrap();
+                let previous = written.entry(path).or_insert(status);
+                if *previous == WriteStatus::Unchanged {
+                    *previous = status;
+                }
+            }
+        }
+    }
+
+    pub fn summary(&self) -> WriteSummary {
+        let mut summary = WriteSummary::default();
+        for status in self.written.lock().unwrap().values() {
+            match status {
+                WriteStatus::Created => summary.created += 1,
+                WriteStatus::Updated => summary.updated += 1,
+                WriteStatus::Unchanged => summary.unchanged += 1,
+            }
+        }
+        summary
+    }
+
+    /// Every file written so far, sorted by path; later writes to the same path replace earlier ones.
+    pub fn recorded(&self) -> Vec<(PathBuf, String)> {
+        self.recorded
+            .as_ref()
+            .map(|recorded| recorded.lock().unwrap().clone().into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// The recorded files that would create or change something on disk.
+    pub fn pending(&self) -> Vec<PendingWrite> {
+        self.recorded()
+            .into_iter()
+            .filter_map(|(path, generated)| {
+                let on_disk = fs::read_to_string(&path).ok();
+                (on_disk.as_deref() != Some(generated.as_str())).then_some(PendingWrite {
+                    path,
+                    on_disk,
+                    generated,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The scripts and templates a run used from the config directory, so watch mode knows what to regenerate.
+#[derive(Default, Clone)]
+pub struct Dependencies {
+    files: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl Dependencies {
+    /// Records a file even if it doesn't exist (yet), so creating it later counts as a change.
+    pub fn record(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let path = match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) => dir
+                .canonicalize()
+                .map(|dir| dir.join(name))
+                .unwrap_or(path.to_path_buf()),
+            _ => path.to_path_buf(),
+        };
+        self.files.lock().unwrap().insert(path);
+    }
+
+    pub fn files(&self) -> BTreeSet<PathBuf> {
+        self.files.lock().unwrap().clone()
+    }
+}
+
+/// A recorded file whose generated content differs from what is on disk.
+#[derive(Debug, Clone)]
+pub struct PendingWrite {
+    pub path: PathBuf,
+    /// `None` if the file doesn't exist yet.
+    pub on_disk: Option<String>,
+    pub generated: String,
+}
+
+impl PendingWrite {
+    pub fn is_new(&self) -> bool {
+        self.on_disk.is_none()
+    }
 }
 
 #[derive(Default, Clone)]
@@ -18,10 +179,10 @@ impl StringWriter {
     }
 
     pub fn add_string_writer(&mut self, txt: StringWriter) {
-        self.text += &txt.get();
+        self.text += &txt.text;
     }
 
-    pub fn get(&self) -> String {
+    pub fn get(&mut self) -> String {
         self.text.clone()
     }
 }
@@ -39,28 +200,27 @@ impl CustomType for StringWriter {
 #[derive(Clone)]
 pub struct OutputWriter {
     output_path: String,
-    config_path: String,
+    search_path: SearchPath,
     text: String,
-}
-
-macro_rules! path_to_str {
-    ($name:ident) => {
-        $name
-            .canonicalize()
-            .unwrap()
-            .as_os_str()
-            .to_os_string()
-            .into_string()
-            .unwrap()
-    };
+    sink: OutputSink,
+    dependencies: Dependencies,
 }
 
 impl OutputWriter {
-    pub fn new(output_path: PathBuf, config_path: PathBuf) -> Self {
+    pub fn new(
+        output_path: PathBuf,
+        search_path: SearchPath,
+        sink: OutputSink,
+        dependencies: Dependencies,
+    ) -> Self {
+        // the output directory may not exist until the first file is written into it
+        let output_path = output_path.canonicalize().unwrap_or(output_path);
         OutputWriter {
-            output_path: path_to_str!(output_path),
-            config_path: path_to_str!(config_path),
+            output_path: output_path.into_os_string().into_string().unwrap(),
+            search_path,
             text: "".into(),
+            sink,
+            dependencies,
         }
     }
 
@@ -72,18 +232,34 @@ impl OutputWriter {
         self.text.clone()
     }
 
+    /// The first `temp` along the search path, recording every candidate so watch mode notices when one appears.
+    fn find_template(&self, temp: &str) -> Option<Found> {
+        for candidate in self.search_path.candidates(temp) {
+            self.dependencies.record(candidate);
+        }
+        self.search_path.find(temp)
+    }
+
     pub fn has_template(&mut self, temp: String) -> bool {
-        let file = format!("{}{}{}", self.config_path, std::path::MAIN_SEPARATOR, temp);
-        fs::metadata(file).is_ok()
+        self.find_template(&temp).is_some()
     }
 
-    pub fn template_from_file(&mut self, temp: String) -> OutputTemplate {
-        let file = format!("{}{}{}", self.config_path, std::path::MAIN_SEPARATOR, temp);
-        OutputTemplate {
-            pattern: fs::read_to_string(file.clone())
-                .unwrap_or_else(|_| panic!("File not found {:?}", file)),
+    pub fn template_from_file(
+        &mut self,
+        temp: String,
+    ) -> Result<OutputTemplate, Box<rhai::EvalAltResult>> {
+        let file = self.find_template(&temp).ok_or(format!(
+            "template `{}` isn't in any script folder and isn't built in",
+            temp
+        ))?;
+        let pattern = file
+            .read()
+            .map_err(|e| format!("cannot read {}: {}", file.path().display(), e))?;
+
+        Ok(OutputTemplate {
+            pattern,
             vars: HashMap::default(),
-        }
+        })
     }
 
     pub fn embed(&mut self, temp: OutputTemplate) {
@@ -91,10 +267,23 @@ impl OutputWriter {
     }
 
     pub fn write_to(&mut self, path: String) {
-        write_file(
-            format!("{}{}{}", self.output_path, std::path::MAIN_SEPARATOR, path),
-            self.get(),
-        );
+        let file = format!("{}{}{}", self.output_path, std::path::MAIN_SEPARATOR, path);
+
+        let text = match fs::read_to_string(&file) {
+            Ok(existing) => {
+                let merged = merge_user_regions(&existing, &self.get());
+                for orphan in merged.orphans {
+                    eprintln!(
+                        "warning: user region `{}` in {} is no longer in the template, its content was dropped",
+                        orphan, file
+                    );
+                }
+                merged.text
+            }
+            Err(_) => self.get(),
+        };
+
+        self.sink.write(file, text);
     }
 
     pub fn clear(&mut self) {
@@ -132,7 +321,7 @@ impl OutputTemplate {
     }
 
     pub fn put_string_writer(&mut self, name: &str, value: StringWriter) {
-        self.vars.insert(name.to_owned(), value.get());
+        self.vars.insert(name.to_owned(), value.text);
     }
 
     pub fn finish(self) -> String {
@@ -156,3 +345,60 @@ impl CustomType for OutputTemplate {
             .with_fn("finish", OutputTemplate::finish);
     }
 }
+
+#[cfg(test)]
+mod output_tests {
+    use std::fs;
+
+    use crate::output::{write_file, OutputSink, WriteStatus};
+
+    #[test]
+    fn skip_identical_writes() {
+        let path = std::env::temp_dir().join(format!("moho-write-{}.h", std::process::id()));
+        let p = path.to_str().unwrap().to_string();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(write_file(p.clone(), "a".into()), WriteStatus::Created);
+        assert_eq!(write_file(p.clone(), "a".into()), WriteStatus::Unchanged);
+        assert_eq!(write_file(p.clone(), "b".into()), WriteStatus::Updated);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "b");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn dry_run_leaves_disk_alone() {
+        let dir = std::env::temp_dir().join(format!("moho-dry-run-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Same.h"), "same").unwrap();
+        fs::write(dir.join("Changed.h"), "old").unwrap();
+
+        let sink = OutputSink::memory();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        sink.write(path("Same.h"), "same".into());
+        sink.write(path("Changed.h"), "first".into());
+        sink.write(path("Changed.h"), "new".into());
+        sink.write(path("New.h"), "new".into());
+
+        // nothing is written, and the last write to a path is what counts
+        assert_eq!(fs::read_to_string(dir.join("Changed.h")).unwrap(), "old");
+        assert!(!dir.join("New.h").exists());
+        assert_eq!(sink.recorded().len(), 3);
+
+        let pending = sink
+            .pending()
+            .into_iter()
+            .map(|write| (write.path, write.on_disk, write.generated))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pending,
+            vec![
+                (dir.join("Changed.h"), Some("old".into()), "new".into()),
+                (dir.join("New.h"), None, "new".into()),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
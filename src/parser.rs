@@ -1,12 +1,15 @@
-use std::fmt::Display;
+use std::{fmt::Display, num::IntErrorKind};
 
 use pest::{
-    error::LineColLocation,
+    error::InputLocation,
     iterators::{Pair, Pairs},
-    Parser, Span,
+    Parser,
 };
 
-use crate::grammar::{MohoGrammar, Rule};
+use crate::{
+    error::{ErrorCode, Location, MohoError},
+    grammar::{MohoGrammar, Rule},
+};
 
 macro_rules! rhai_print {
     ($name:ident) => {
@@ -22,15 +25,26 @@ macro_rules! rhai_print {
     };
 }
 
+/// A string or char literal, both as it's written, with its quotes and escapes, and as the text it stands for.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Literal {
+    pub raw: String,
+    pub text: String,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     Default,
     Nullptr,
-    Char(u8),
+    Char(Literal),
     Bool(bool),
-    Float(f64),
-    Str(String),
-    Integer(isize),
+    /// The number, and how it's spelled in the .moho file, e.g. `1.5f`.
+    Float(f64, String),
+    Str(Literal),
+    /// The number, and how it's spelled in the .moho file, e.g. `0xFFu`.
+    Integer(i128, String),
+    /// A bare name, e.g. `OnRep_Health` or `EMode::Fast`, used as it is.
+    Identifier(String),
 }
 
 impl Value {
@@ -40,9 +54,10 @@ impl Value {
             Value::Nullptr => "nullptr".into(),
             Value::Char(_) => "char".into(),
             Value::Bool(_) => "bool".into(),
-            Value::Float(_) => "float".into(),
+            Value::Float(..) => "float".into(),
             Value::Str(_) => "string".into(),
-            Value::Integer(_) => "integer".into(),
+            Value::Integer(..) => "integer".into(),
+            Value::Identifier(_) => "identifier".into(),
         }
     }
 
@@ -53,6 +68,40 @@ impl Value {
     pub fn print(&mut self) -> String {
         format!("{}", self)
     }
+
+    /// The value the way it's written in the .moho file.
+    pub fn raw(&mut self) -> String {
+        match self {
+            Value::Float(_, spelling) | Value::Integer(_, spelling) => spelling.clone(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// What a string or char stands for, without quotes and with its escapes resolved.
+    pub fn text(&mut self) -> String {
+        match self {
+            Value::Str(literal) | Value::Char(literal) => literal.text.clone(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// The value as C++ code where an `FString` is expected; strings are wrapped in `TEXT(...)`.
+    pub fn as_cpp(&mut self) -> String {
+        match self {
+            Value::Str(literal) => format!("TEXT({})", literal.raw),
+            _ => self.to_string(),
+        }
+    }
+
+    /// The value as C++ code for a field of type `typ`; strings are only wrapped in `TEXT(...)`
+    /// for `FString`, `FName`, and `string` (which is an `FString` too), e.g. not for a `const char*`.
+    pub fn as_cpp_for(&mut self, typ: Type) -> String {
+        match typ {
+            Type::String => self.as_cpp(),
+            Type::Class(name) if name == "FString" || name == "FName" => self.as_cpp(),
+            _ => self.to_string(),
+        }
+    }
 }
 impl rhai::CustomType for Value {
     fn build(mut builder: rhai::TypeBuilder<Self>) {
@@ -60,6 +109,10 @@ impl rhai::CustomType for Value {
             .with_name("Value")
             .with_fn("pure", Value::pure)
             .with_fn("as_value", Value::print)
+            .with_fn("as_cpp", Value::as_cpp)
+            .with_fn("as_cpp", Value::as_cpp_for)
+            .with_fn("raw", Value::raw)
+            .with_fn("text", Value::text)
             .with_fn("is_empty", Value::is_empty)
             .on_debug(|s| format!("{:?}", s))
             .on_print(|s| format!("{:?}", s));
@@ -72,12 +125,15 @@ impl Display for Value {
             match self {
                 Value::Default => "(*void)0".into(),
                 Value::Nullptr => "nullptr".into(),
-                Value::Char(c) => format!("(char){}", *c),
+                Value::Char(c) => c.raw.clone(),
                 Value::Bool(true) => "true".into(),
                 Value::Bool(false) => "false".into(),
-                Value::Float(f) => format!("{}", *f),
-                Value::Str(s) => s.clone(),
-                Value::Integer(i) => format!("{}", *i),
+                // C++ only knows `'` as a digit separator
+                Value::Float(_, spelling) | Value::Integer(_, spelling) => {
+                    spelling.replace('_', "'")
+                }
+                Value::Str(s) => s.raw.clone(),
+                Value::Identifier(name) => name.clone(),
             }
             .as_str(),
         )
@@ -97,6 +153,7 @@ pub enum Type {
     Pointer(Box<Type>, u32),
     Reference(Box<Type>),
     Class(String),
+    Generic(String, Vec<Type>),
 }
 
 impl Type {
@@ -120,6 +177,55 @@ impl Type {
             Type::Pointer(_, _) => "pointer".into(),
             Type::Reference(_) => "reference".into(),
             Type::Class(c) => c.clone(),
+            Type::Generic(_, _) => "generic".into(),
+        }
+    }
+
+    pub fn name(&mut self) -> String {
+        match self {
+            Type::Class(c) => c.clone(),
+            Type::Generic(g, _) => g.clone(),
+            _ => self.cpp(),
+        }
+    }
+
+    pub fn args(&mut self) -> Vec<Type> {
+        match self {
+            Type::Generic(_, args) => args.clone(),
+            _ => vec![],
+        }
+    }
+
+    pub fn arg(&mut self, index: i64) -> Result<Type, Box<rhai::EvalAltResult>> {
+        self.args()
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| format!("Type {} has no argument {}", self.cpp(), index).into())
+    }
+
+    /// Spells the type out the way the default type templates write it in a header, e.g.
+    /// `TMap<FName, TArray<TObjectPtr<AActor>>>`: `string` is an `FString`, and pointers are `TObjectPtr`s.
+    pub fn cpp(&mut self) -> String {
+        match self {
+            Type::Void => "void".into(),
+            Type::Char => "char".into(),
+            Type::Bool => "bool".into(),
+            Type::Float => "float".into(),
+            Type::String => "FString".into(),
+            Type::Integer => "int".into(),
+            Type::Array(a) => format!("TArray<{}>", a.cpp()),
+            Type::Matrix(m) => format!("TArray<TArray<{}>>", m.cpp()),
+            Type::Pointer(p, _) => format!("TObjectPtr<{}>", p.cpp()),
+            Type::Reference(r) => format!("{}&", r.cpp()),
+            Type::Class(c) => c.clone(),
+            Type::Generic(g, args) => format!(
+                "{}<{}>",
+                g,
+                args.iter_mut()
+                    .map(|a| a.cpp())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 
@@ -129,7 +235,7 @@ impl Type {
             Type::Matrix(m) => m.as_ref().clone(),
             Type::Pointer(p, _) => p.as_ref().clone(),
             Type::Reference(r) => r.as_ref().clone(),
-            Type::Class(_) => self.clone(),
+            Type::Class(_) | Type::Generic(_, _) => self.clone(),
             _ => unreachable!("`inner` should never be called on primitive types"),
         }
     }
@@ -142,6 +248,10 @@ impl rhai::CustomType for Type {
             .with_fn("pure", Type::pure)
             .with_fn("inner", Type::inner)
             .with_fn("is_primitive", Type::is_primitive)
+            .with_fn("name", Type::name)
+            .with_fn("args", Type::args)
+            .with_fn("arg", Type::arg)
+            .with_fn("cpp", Type::cpp)
             .on_debug(|s| format!("{:?}", s))
             .on_print(|s| format!("{:?}", s));
     }
@@ -170,6 +280,76 @@ impl Property {
     pub fn get_value(&mut self) -> Option<Value> {
         self.value.clone()
     }
+
+    /// The value as it would be used in code: strings lose their quotes, and a missing value is empty.
+    pub fn text(&self) -> String {
+        match self.value.clone() {
+            Some(mut value) => value.text(),
+            None => "".into(),
+        }
+    }
+}
+
+/// The text of the property called `name`, if there is one. Properties are collected from the outermost
+/// block in, so the last one is the innermost, and wins.
+fn find_property(properties: &[Property], name: &str) -> Option<String> {
+    properties
+        .iter()
+        .rev()
+        .find(|p| p.name == name)
+        .map(|p| p.text())
+}
+
+/// The properties with `meta` set or not, without the ones an inner block or the declaration overrides.
+fn innermost_properties(properties: &[Property], meta: bool) -> Vec<Property> {
+    properties
+        .iter()
+        .enumerate()
+        .filter(|(at, p)| {
+            p.meta == meta
+                && !properties[at + 1..]
+                    .iter()
+                    .any(|later| later.name == p.name)
+        })
+        .map(|(_, p)| p.clone())
+        .collect()
+}
+
+/// A C++ access level, from a `public:` label or an `[#Access=...]` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Public,
+    Protected,
+    Private,
+}
+
+impl Access {
+    pub fn parse(text: &str) -> Option<Access> {
+        match text.trim_end_matches(':').trim() {
+            "public" => Some(Access::Public),
+            "protected" => Some(Access::Protected),
+            "private" => Some(Access::Private),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Access::Public => "public",
+            Access::Protected => "protected",
+            Access::Private => "private",
+        }
+    }
+}
+
+/// The `[#Access=...]` property wins over the label a declaration is under, and either wins over the default.
+fn resolve_access(properties: &[Property], label: Option<Access>, default: Access) -> String {
+    find_property(properties, "Access")
+        .and_then(|access| Access::parse(&access))
+        .or(label)
+        .unwrap_or(default)
+        .as_str()
+        .to_string()
 }
 
 #[derive(Debug, Clone)]
@@ -181,6 +361,25 @@ pub enum Declaration {
 
 rhai_print!(Declaration);
 
+impl Declaration {
+    /// Gives `doc` to every field and method in here that doesn't have a doc comment of its own.
+    fn inherit_doc(&mut self, doc: &str) {
+        match self {
+            Declaration::Block(block) => {
+                for decl in block.inner.iter_mut() {
+                    decl.inherit_doc(doc);
+                }
+            }
+            Declaration::Field(field) => {
+                field.doc.get_or_insert_with(|| doc.to_string());
+            }
+            Declaration::Method(method) => {
+                method.doc.get_or_insert_with(|| doc.to_string());
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Field {
     pub is_static: bool,
@@ -188,6 +387,10 @@ pub struct Field {
     pub name: String,
     pub typ: Type,
     pub value: Option<Value>,
+    /// The access label the field is under, if there is one.
+    pub access: Option<Access>,
+    /// The `///` comment above the field, if there is one.
+    pub doc: Option<String>,
 }
 
 rhai_print!(Field);
@@ -197,10 +400,16 @@ impl rhai::CustomType for Field {
         builder
             .with_name("Field")
             .with_get("properties", Field::get_properties)
+            .with_get("specifiers", Field::get_specifiers)
+            .with_get("meta", Field::get_meta_properties)
+            .with_get("access", Field::get_access)
+            .with_get("doc", Field::get_doc)
             .with_get("name", Field::get_name)
             .with_get("type", Field::get_type)
             .with_get("value", Field::get_value)
             .with_get("is_static", Field::get_is_static)
+            .with_fn("has_property", Field::has_property)
+            .with_fn("property", Field::property)
             .on_debug(|s| format!("{:?}", s))
             .on_print(|s| format!("{:?}", s));
     }
@@ -211,6 +420,14 @@ impl Field {
         self.properties.clone()
     }
 
+    pub fn get_specifiers(&mut self) -> Vec<Property> {
+        innermost_properties(&self.properties, false)
+    }
+
+    pub fn get_meta_properties(&mut self) -> Vec<Property> {
+        innermost_properties(&self.properties, true)
+    }
+
     pub fn get_name(&mut self) -> String {
         self.name.clone()
     }
@@ -229,6 +446,24 @@ impl Field {
     pub fn get_is_static(&mut self) -> bool {
         self.is_static
     }
+
+    /// `public`, `protected`, or `private`; fields are protected unless they say otherwise.
+    pub fn get_access(&mut self) -> String {
+        resolve_access(&self.properties, self.access, Access::Protected)
+    }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
+
+    pub fn has_property(&mut self, name: &str) -> bool {
+        find_property(&self.properties, name).is_some()
+    }
+
+    /// The value of a property without the quotes around strings, or empty if the field doesn't have it.
+    pub fn property(&mut self, name: &str) -> String {
+        find_property(&self.properties, name).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -279,6 +514,12 @@ pub struct Method {
     pub name: String,
     pub returns: String,
     pub arguments: Vec<Argument>,
+    /// The access label the method is under, if there is one.
+    pub access: Option<Access>,
+    /// The `///` comment above the method, if there is one.
+    pub doc: Option<String>,
+    /// Where the method is in its .moho file, for errors found after parsing.
+    pub location: Option<Location>,
 }
 
 rhai_print!(Method);
@@ -293,7 +534,14 @@ impl rhai::CustomType for Method {
             .with_get("type", Method::get_return_type)
             .with_get("arguments", Method::get_arguments)
             .with_get("arguments_as_str", Method::arguments_as_str)
+            .with_get("arguments_with_defaults", Method::arguments_with_defaults)
+            .with_get("argument_types", Method::argument_types)
             .with_get("is_static", Method::get_is_static)
+            .with_get("is_net", Method::is_net)
+            .with_get("access", Method::get_access)
+            .with_get("doc", Method::get_doc)
+            .with_fn("has_property", Method::has_property)
+            .with_fn("property", Method::property)
             .on_debug(|s| format!("{:?}", s))
             .on_print(|s| format!("{:?}", s));
     }
@@ -301,15 +549,11 @@ impl rhai::CustomType for Method {
 
 impl Method {
     pub fn get_properties(&mut self) -> Vec<Property> {
-        self.properties
-            .iter()
-            .filter(|p| !p.meta)
-            .cloned()
-            .collect()
+        innermost_properties(&self.properties, false)
     }
 
     pub fn get_meta_properties(&mut self) -> Vec<Property> {
-        self.properties.iter().filter(|p| p.meta).cloned().collect()
+        innermost_properties(&self.properties, true)
     }
 
     pub fn get_name(&mut self) -> String {
@@ -333,9 +577,55 @@ impl Method {
             .to_string()
     }
 
+    /// The arguments as they're declared in a header, with their default values.
+    pub fn arguments_with_defaults(&mut self) -> String {
+        self.arguments
+            .iter()
+            .map(|a| match &a.value {
+                Some(value) => format!("{} {} = {}", a.typ, a.name, value),
+                None => format!("{} {}", a.typ, a.name),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Just the argument types, e.g. `const UObject*, int32`, to tell overloads apart.
+    pub fn argument_types(&mut self) -> String {
+        self.arguments
+            .iter()
+            .map(|a| a.typ.clone())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub fn get_is_static(&mut self) -> bool {
         self.is_static
     }
+
+    /// `public`, `protected`, or `private`; methods are public unless they say otherwise.
+    pub fn get_access(&mut self) -> String {
+        resolve_access(&self.properties, self.access, Access::Public)
+    }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
+
+    /// Whether this is an RPC, called on the server, the owning client, or everyone.
+    pub fn is_net(&mut self) -> bool {
+        ["Server", "Client", "NetMulticast"]
+            .iter()
+            .any(|net| find_property(&self.properties, net).is_some())
+    }
+
+    pub fn has_property(&mut self, name: &str) -> bool {
+        find_property(&self.properties, name).is_some()
+    }
+
+    /// The value of a property without the quotes around strings, or empty if the method doesn't have it.
+    pub fn property(&mut self, name: &str) -> String {
+        find_property(&self.properties, name).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -383,6 +673,8 @@ impl Block {
                         name: f.name,
                         typ: f.typ,
                         value: f.value,
+                        access: f.access,
+                        doc: f.doc,
                     }))
                 }
                 Declaration::Method(m) => {
@@ -397,6 +689,9 @@ impl Block {
                         name: m.name,
                         returns: m.returns,
                         arguments: m.arguments,
+                        access: m.access,
+                        doc: m.doc,
+                        location: m.location,
                     }))
                 }
                 _ => None,
@@ -457,6 +752,7 @@ pub struct Class {
     pub name: String,
     pub inherit: Vec<String>,
     pub inner: Block,
+    pub doc: Option<String>,
 }
 
 rhai_print!(Class);
@@ -467,7 +763,8 @@ impl rhai::CustomType for Class {
             .with_name("Class")
             .with_get("name", Class::get_name)
             .with_get("inherit", Class::get_inherit)
-            .with_get("inner", Class::get_inner);
+            .with_get("inner", Class::get_inner)
+            .with_get("doc", Class::get_doc);
     }
 }
 
@@ -483,60 +780,341 @@ impl Class {
     pub fn get_inner(&mut self) -> Block {
         self.inner.clone()
     }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub name: String,
+    pub inherit: Vec<String>,
+    pub inner: Block,
+    pub doc: Option<String>,
+}
+
+rhai_print!(Struct);
+
+impl rhai::CustomType for Struct {
+    fn build(mut builder: rhai::TypeBuilder<Self>) {
+        builder
+            .with_name("Struct")
+            .with_get("name", Struct::get_name)
+            .with_get("inherit", Struct::get_inherit)
+            .with_get("inner", Struct::get_inner)
+            .with_get("doc", Struct::get_doc);
+    }
+}
+
+impl Struct {
+    pub fn get_name(&mut self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_inherit(&mut self) -> Vec<String> {
+        self.inherit.clone()
+    }
+
+    pub fn get_inner(&mut self) -> Block {
+        self.inner.clone()
+    }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct TranslationUnit(pub Vec<Class>);
+pub struct EnumEntry {
+    pub properties: Vec<Property>,
+    pub name: String,
+    pub value: Option<Value>,
+    pub doc: Option<String>,
+}
 
-#[derive(Debug)]
-pub struct MohoParser;
+rhai_print!(EnumEntry);
+
+impl rhai::CustomType for EnumEntry {
+    fn build(mut builder: rhai::TypeBuilder<Self>) {
+        builder
+            .with_name("EnumEntry")
+            .with_get("properties", EnumEntry::get_properties)
+            .with_get("name", EnumEntry::get_name)
+            .with_get("value", EnumEntry::get_value)
+            .with_get("doc", EnumEntry::get_doc)
+            .on_debug(|s| format!("{:?}", s))
+            .on_print(|s| format!("{:?}", s));
+    }
+}
+
+impl EnumEntry {
+    pub fn get_properties(&mut self) -> Vec<Property> {
+        self.properties.clone()
+    }
+
+    pub fn get_name(&mut self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_value(&mut self) -> Value {
+        match self.value.as_ref() {
+            Some(v) => v.clone(),
+            None => Value::Default,
+        }
+    }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
+}
 
 #[derive(Debug, Clone)]
-pub enum MohoError<'a> {
-    TokenizerError(String, LineColLocation),
-    ParseArgumentError(String, Span<'a>),
-    ParsePropertyError(String, Span<'a>),
-    ParseValueError(String, Span<'a>),
-    ParseClassError(String, Span<'a>),
-    DeclarationError(String, Span<'a>),
-    ParseTypeError(String, Span<'a>),
-    ParseFieldError(String, Span<'a>),
+pub struct Enum {
+    pub name: String,
+    pub underlying: Option<String>,
+    pub properties: Vec<Property>,
+    pub entries: Vec<EnumEntry>,
+    pub doc: Option<String>,
 }
 
+rhai_print!(Enum);
+
+impl rhai::CustomType for Enum {
+    fn build(mut builder: rhai::TypeBuilder<Self>) {
+        builder
+            .with_name("Enum")
+            .with_get("name", Enum::get_name)
+            .with_get("underlying", Enum::get_underlying)
+            .with_get("properties", Enum::get_properties)
+            .with_get("entries", Enum::get_entries)
+            .with_get("doc", Enum::get_doc);
+    }
+}
+
+impl Enum {
+    pub fn get_name(&mut self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_underlying(&mut self) -> String {
+        self.underlying.clone().unwrap_or_default()
+    }
+
+    pub fn get_properties(&mut self) -> Vec<Property> {
+        self.properties.clone()
+    }
+
+    pub fn get_entries(&mut self) -> Vec<EnumEntry> {
+        self.entries.clone()
+    }
+
+    pub fn get_doc(&mut self) -> String {
+        self.doc.clone().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TranslationUnit {
+    pub classes: Vec<Class>,
+    pub structs: Vec<Struct>,
+    pub enums: Vec<Enum>,
+}
+
+#[derive(Debug)]
+pub struct MohoParser;
+
 impl MohoParser {
+    /// Parses the whole input, stopping at the first error.
     pub fn apply(input: &str) -> Result<TranslationUnit, MohoError> {
-        let mut result = vec![];
-        match MohoGrammar::parse(Rule::moho, input) {
-            Ok(parsed) => {
-                for pair in parsed {
-                    for class in pair.into_inner() {
-                        result.push(MohoParser::parse_class(class)?);
+        let (result, errors) = Self::apply_recovering(input);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    /// Parses the whole input, reporting every error in it. Classes, structs, and enums with
+    /// errors in them are left out of the returned translation unit; everything else is kept.
+    /// Syntax errors only say which tokens were expected (e.g. "expected `;`") once
+    /// `pest::set_error_detail(true)` has been called, which `main` does at startup.
+    pub fn apply_recovering(input: &str) -> (TranslationUnit, Vec<MohoError>) {
+        let mut result = TranslationUnit::default();
+        let mut errors = vec![];
+
+        let parsed = match MohoGrammar::parse(Rule::moho, input) {
+            Ok(parsed) => parsed,
+            Err(err) => return (result, vec![Self::syntax_error(err, input, 0)]),
+        };
+
+        for pair in parsed {
+            for item in pair.into_inner() {
+                let mut item_errors = vec![];
+                match item.as_rule() {
+                    Rule::struct_decl => match Self::parse_struct(item, &mut item_errors) {
+                        Ok(s) if item_errors.is_empty() => result.structs.push(s),
+                        Ok(_) => {}
+                        Err(e) => item_errors.push(e),
+                    },
+                    Rule::enum_decl => match Self::parse_enum(item) {
+                        Ok(e) => result.enums.push(e),
+                        Err(e) => item_errors.push(e),
+                    },
+                    Rule::class => match Self::parse_class(item, &mut item_errors) {
+                        Ok(c) if item_errors.is_empty() => result.classes.push(c),
+                        Ok(_) => {}
+                        Err(e) => item_errors.push(e),
+                    },
+                    Rule::bad_item => {
+                        item_errors.push(Self::reparse_error(item, Rule::strict_item));
                     }
+                    Rule::doc => item_errors.push(Self::dangling_doc(item)),
+                    _ => {}
                 }
 
-                Ok(TranslationUnit(result))
+                errors.extend(item_errors);
+            }
+        }
+
+        (result, errors)
+    }
+
+    /// Skipped text is parsed again on its own, strictly, to find out what was wrong with it.
+    fn reparse_error(skipped: Pair<'_, Rule>, rule: Rule) -> MohoError {
+        let span = skipped.as_span();
+        match MohoGrammar::parse(rule, span.as_str()) {
+            Err(err) => Self::syntax_error(err, span.get_input(), span.start()),
+            Ok(_) => MohoError::spanned(
+                ErrorCode::Syntax,
+                format!("unexpected {:?}", span.as_str().trim()),
+                span,
+            ),
+        }
+    }
+
+    /// Turns a pest error into a syntax error; `offset` is where the parsed text starts in `input`.
+    fn syntax_error(err: pest::error::Error<Rule>, input: &str, offset: usize) -> MohoError {
+        // prefer the literal tokens pest expected (e.g. `;`) over the rules it expected
+        if let Some(attempts) = err.parse_attempts() {
+            let tokens = attempts
+                .expected_tokens()
+                .into_iter()
+                .map(|token| token.to_string())
+                .filter(|token| !token.trim().is_empty() && !Self::is_token_class(token))
+                .map(|token| format!("`{}`", token))
+                .collect::<Vec<_>>();
+
+            if !tokens.is_empty() {
+                let pos = offset + attempts.max_position;
+                return MohoError::new(
+                    ErrorCode::Syntax,
+                    format!("expected {}", tokens.join(", ")),
+                )
+                .at(Location::from_range(input, pos..pos + 1));
             }
-            Err(err) => Err(MohoError::TokenizerError(err.to_string(), err.line_col)),
+        }
+
+        let err = err.renamed_rules(Self::describe_rule);
+        let range = match err.location {
+            InputLocation::Pos(pos) => offset + pos..offset + pos + 1,
+            InputLocation::Span((start, end)) => offset + start..offset + end,
+        };
+
+        MohoError::new(ErrorCode::Syntax, err.variant.message().to_string())
+            .at(Location::from_range(input, range))
+    }
+
+    /// Character ranges (`a..z`), builtins, and comments (which can go anywhere) are noise next to
+    /// the literal tokens we can name.
+    fn is_token_class(token: &str) -> bool {
+        let chars = token.chars().collect::<Vec<_>>();
+        matches!(token, "BUILTIN_RULE" | "//" | "/*" | "///")
+            || (chars.len() == 4 && chars[1] == '.' && chars[2] == '.')
+    }
+
+    /// Human-readable names for the rules pest reports as expected, e.g. "expected `;`".
+    fn describe_rule(rule: &Rule) -> String {
+        match rule {
+            Rule::EOI => "end of file".into(),
+            Rule::identifier | Rule::name => "identifier".into(),
+            Rule::value => "value".into(),
+            Rule::property => "property".into(),
+            Rule::properties => "properties `[...]`".into(),
+            Rule::inheritance => "superclass".into(),
+            Rule::class => "`class`".into(),
+            Rule::struct_decl => "`struct`".into(),
+            Rule::enum_decl => "`enum`".into(),
+            Rule::enum_entry => "enum entry".into(),
+            Rule::declaration => "declaration".into(),
+            Rule::field_decl => "field".into(),
+            Rule::method_decl => "method".into(),
+            Rule::block_decl => "block `{...}`".into(),
+            Rule::type_decl | Rule::dont_care_type_decl => "type".into(),
+            Rule::argument | Rule::arguments => "argument".into(),
+            Rule::declaration_end => "`;`".into(),
+            Rule::entry_separator => "`,`".into(),
+            other => format!("{:?}", other),
         }
     }
 
-    fn parse_class(class: Pair<'_, Rule>) -> Result<Class, MohoError> {
+    fn parse_class(class: Pair<'_, Rule>, errors: &mut Vec<MohoError>) -> Result<Class, MohoError> {
         assert_eq!(class.as_rule(), Rule::class);
 
+        let (name, inherit, inner, doc) = Self::parse_record(class, "class", errors)?;
+        Ok(Class {
+            name,
+            inherit,
+            inner,
+            doc,
+        })
+    }
+
+    fn parse_struct(
+        decl: Pair<'_, Rule>,
+        errors: &mut Vec<MohoError>,
+    ) -> Result<Struct, MohoError> {
+        assert_eq!(decl.as_rule(), Rule::struct_decl);
+
+        let (name, inherit, inner, doc) = Self::parse_record(decl, "struct", errors)?;
+        Ok(Struct {
+            name,
+            inherit,
+            inner,
+            doc,
+        })
+    }
+
+    /// Classes and structs share their syntax: a name, optional inheritance, and a body of declarations.
+    /// A broken declaration doesn't stop the rest of the body from being read; its error goes into `errors`.
+    fn parse_record(
+        record: Pair<'_, Rule>,
+        kind: &str,
+        errors: &mut Vec<MohoError>,
+    ) -> Result<(String, Vec<String>, Block, Option<String>), MohoError> {
         let mut name = "";
         let mut inherit = vec![];
+        let mut doc = None;
 
         let mut result = Block {
             properties: vec![],
             inner: vec![],
         };
 
-        let span = class.as_span();
-        for pair in class.into_inner() {
+        let span = record.as_span();
+        // like in C++, everything in a struct is public until a label says otherwise
+        let mut access = (kind == "struct").then_some(Access::Public);
+        for pair in record.into_inner() {
             if matches!(pair.as_rule(), Rule::properties) {
                 result
                     .properties
                     .extend(MohoParser::parse_properties(pair)?);
+            } else if matches!(pair.as_rule(), Rule::doc) {
+                // one in the body is a doc comment that isn't above anything
+                if name.is_empty() {
+                    doc = Some(Self::parse_doc(pair));
+                } else {
+                    errors.push(Self::dangling_doc(pair));
+                }
             } else if matches!(pair.as_rule(), Rule::name) {
                 name = pair.as_str();
             } else if matches!(pair.as_rule(), Rule::inheritance) {
@@ -546,28 +1124,149 @@ impl MohoParser {
                     .map(|p| p.trim().to_string())
                     .filter(|p| !p.is_empty())
                     .collect()
-            } else if matches!(pair.as_rule(), Rule::declaration) {
-                result.inner.push(Self::parse_declaration(pair)?);
+            } else if matches!(pair.as_rule(), Rule::access_label) {
+                access = Access::parse(pair.as_str());
+            } else if matches!(pair.as_rule(), Rule::declaration | Rule::bad_declaration) {
+                match Self::parse_declaration(pair, access, errors) {
+                    Ok(decl) => result.inner.push(decl),
+                    Err(e) => errors.push(e),
+                }
             } else {
-                return Err(MohoError::ParseClassError(
-                    format!("Cannot parse {:?} in class.", pair),
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidClass,
+                    format!("Cannot parse {:?} in {}.", pair, kind),
                     span,
                 ));
             }
         }
 
         if name.is_empty() {
-            return Err(MohoError::ParseClassError(
-                "Class name missing.".into(),
+            return Err(MohoError::spanned(
+                ErrorCode::InvalidClass,
+                format!("Name missing in {}.", kind),
                 span,
             ));
         }
 
-        Ok(Class {
-            name: name.to_string(),
-            inherit,
-            inner: result,
-        })
+        Ok((name.to_string(), inherit, result, doc))
+    }
+
+    fn parse_enum(decl: Pair<'_, Rule>) -> Result<Enum, MohoError> {
+        assert_eq!(decl.as_rule(), Rule::enum_decl);
+
+        let mut result = Enum {
+            name: "".into(),
+            underlying: None,
+            properties: vec![],
+            entries: vec![],
+            doc: None,
+        };
+
+        let span = decl.as_span();
+        for pair in decl.into_inner() {
+            if matches!(pair.as_rule(), Rule::properties) {
+                result
+                    .properties
+                    .extend(MohoParser::parse_properties(pair)?);
+            } else if matches!(pair.as_rule(), Rule::doc) {
+                // one after the name is a doc comment after the last entry
+                if !result.name.is_empty() {
+                    return Err(Self::dangling_doc(pair));
+                }
+                result.doc = Some(Self::parse_doc(pair));
+            } else if matches!(pair.as_rule(), Rule::name) {
+                result.name = pair.as_str().to_string();
+            } else if matches!(pair.as_rule(), Rule::underlying) {
+                result.underlying = Some(pair.as_str().trim().to_string());
+            } else if matches!(pair.as_rule(), Rule::enum_entry) {
+                result.entries.push(Self::parse_enum_entry(pair)?);
+            } else if matches!(pair.as_rule(), Rule::entry_separator) {
+                if let Some(entry) = result.entries.last_mut() {
+                    Self::add_trailing_doc(&mut entry.doc, pair);
+                }
+            } else {
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidEnum,
+                    format!("Cannot parse {:?} in enum.", pair),
+                    span,
+                ));
+            }
+        }
+
+        if result.name.is_empty() {
+            return Err(MohoError::spanned(
+                ErrorCode::InvalidEnum,
+                "Enum name missing.",
+                span,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    fn parse_enum_entry(entry: Pair<'_, Rule>) -> Result<EnumEntry, MohoError> {
+        let span = entry.as_span();
+        let mut result = EnumEntry {
+            properties: vec![],
+            name: "".into(),
+            value: None,
+            doc: None,
+        };
+
+        for pair in entry.into_inner() {
+            if matches!(pair.as_rule(), Rule::properties) {
+                result
+                    .properties
+                    .extend(MohoParser::parse_properties(pair)?);
+            } else if matches!(pair.as_rule(), Rule::doc) {
+                result.doc = Some(Self::parse_doc(pair));
+            } else if matches!(pair.as_rule(), Rule::identifier) {
+                result.name = pair.as_str().trim().to_string();
+            } else if matches!(pair.as_rule(), Rule::value) {
+                result.value = Some(Self::parse_value(&mut pair.into_inner())?);
+            } else {
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidEnum,
+                    format!("Cannot parse {:?} in enum entry.", pair),
+                    span,
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The text of a doc comment, a line for every `///` line, without the slashes and the space after them.
+    fn parse_doc(doc: Pair<'_, Rule>) -> String {
+        doc.into_inner()
+            .map(|line| {
+                let text = line.as_str().trim_start_matches("///");
+                text.strip_prefix(' ')
+                    .unwrap_or(text)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A `///` on the same line after a declaration goes after the doc comment above it, if there's one.
+    fn add_trailing_doc(doc: &mut Option<String>, end: Pair<'_, Rule>) {
+        if let Some(trailing) = end.into_inner().next() {
+            let trailing = Self::parse_doc(trailing);
+            *doc = Some(match doc.take() {
+                Some(doc) => format!("{}\n{}", doc, trailing),
+                None => trailing,
+            });
+        }
+    }
+
+    fn dangling_doc(doc: Pair<'_, Rule>) -> MohoError {
+        MohoError::spanned(
+            ErrorCode::InvalidDeclaration,
+            "this doc comment isn't above anything; put it right above what it documents, or use `//`",
+            doc.as_span(),
+        )
     }
 
     fn parse_properties(pairs: Pair<'_, Rule>) -> Result<Vec<Property>, MohoError> {
@@ -588,15 +1287,16 @@ impl MohoParser {
         }
 
         let Some(name) = iter.next() else {
-            return Err(MohoError::ParsePropertyError(
-                "Expected property name".into(),
+            return Err(MohoError::spanned(
+                ErrorCode::InvalidProperty,
+                "Expected property name",
                 span,
             ));
         };
 
         let value = Self::parse_value(&mut iter)?;
 
-        Ok(Property {
+        let property = Property {
             meta,
             name: name.as_str().to_string(),
             value: if value == Value::Default {
@@ -604,35 +1304,60 @@ impl MohoParser {
             } else {
                 Some(value)
             },
-        })
+        };
+
+        if property.name == "Access" && Access::parse(&property.text()).is_none() {
+            return Err(MohoError::spanned(
+                ErrorCode::InvalidProperty,
+                format!(
+                    "`Access` has to be `public`, `protected`, or `private`, not `{}`",
+                    property.text()
+                ),
+                span,
+            ));
+        }
+
+        Ok(property)
     }
 
     fn match_value(val: Pair<'_, Rule>) -> Result<Value, MohoError> {
         match val.as_rule() {
-            Rule::bool => {
-                Ok(Value::Bool(val.as_str().parse::<bool>().map_err(|e| {
-                    MohoError::ParseValueError(e.to_string(), val.as_span())
-                })?))
-            }
+            Rule::bool => Ok(Value::Bool(val.as_str().parse::<bool>().map_err(|e| {
+                MohoError::spanned(ErrorCode::InvalidValue, e.to_string(), val.as_span())
+            })?)),
 
             Rule::char => {
-                Ok(Value::Char(val.as_str().parse::<u8>().map_err(|e| {
-                    MohoError::ParseValueError(e.to_string(), val.as_span())
-                })?))
+                let literal = Self::parse_literal(&val)?;
+                if literal.text.chars().count() != 1 {
+                    return Err(MohoError::spanned(
+                        ErrorCode::InvalidValue,
+                        format!("{} has to be a single character", literal.raw),
+                        val.as_span(),
+                    ));
+                }
+                Ok(Value::Char(literal))
             }
 
-            Rule::string => Ok(Value::Str(val.as_str().to_string())),
+            Rule::string => Ok(Value::Str(Self::parse_literal(&val)?)),
 
-            Rule::integer => {
-                Ok(Value::Integer(val.as_str().parse::<isize>().map_err(
-                    |e| MohoError::ParseValueError(e.to_string(), val.as_span()),
-                )?))
-            }
+            Rule::identifier => Ok(Value::Identifier(val.as_str().to_string())),
+
+            Rule::integer => Ok(Value::Integer(
+                Self::parse_integer(val.as_str())
+                    .map_err(|e| MohoError::spanned(ErrorCode::InvalidValue, e, val.as_span()))?,
+                val.as_str().to_string(),
+            )),
 
             Rule::float => {
-                Ok(Value::Float(val.as_str().parse::<f64>().map_err(|e| {
-                    MohoError::ParseValueError(e.to_string(), val.as_span())
-                })?))
+                // the grammar makes sure the suffix is the only letter other than the exponent
+                let digits = val
+                    .as_str()
+                    .trim_end_matches(['f', 'F', 'l', 'L'])
+                    .replace(['\'', '_'], "");
+                let float = digits.parse::<f64>().map_err(|e| {
+                    MohoError::spanned(ErrorCode::InvalidValue, e.to_string(), val.as_span())
+                })?;
+                Ok(Value::Float(float, val.as_str().to_string()))
             }
 
             Rule::value => {
@@ -640,20 +1365,142 @@ impl MohoParser {
                 if let Some(val) = val.into_inner().next() {
                     Self::match_value(val)
                 } else {
-                    return Err(MohoError::ParsePropertyError(
-                        "Failed to parse property".into(),
+                    Err(MohoError::spanned(
+                        ErrorCode::InvalidProperty,
+                        "Failed to parse property",
                         span,
-                    ));
+                    ))
                 }
             }
-            _ => Err(MohoError::ParseValueError(
-                "Cannot read value".into(),
+            _ => Err(MohoError::spanned(
+                ErrorCode::InvalidValue,
+                "Cannot read value",
                 val.as_span(),
             )),
         }
     }
 
-    fn parse_argument<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Argument, MohoError<'a>> {
+    /// The value of an integer literal in any base, with its sign, separators, and suffix.
+    fn parse_integer(spelling: &str) -> Result<i128, String> {
+        let (negative, unsigned) = match spelling.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, spelling.trim_start_matches('+')),
+        };
+        let digits = unsigned
+            .trim_end_matches(['u', 'U', 'l', 'L', 'z', 'Z'])
+            .replace(['\'', '_'], "");
+
+        let lower = digits.to_ascii_lowercase();
+        let (radix, digits) = if let Some(hex) = lower.strip_prefix("0x") {
+            (16, hex)
+        } else if let Some(binary) = lower.strip_prefix("0b") {
+            (2, binary)
+        } else if lower.len() > 1 && lower.starts_with('0') {
+            (8, &lower[1..])
+        } else {
+            (10, lower.as_str())
+        };
+
+        let too_big = || format!("{} doesn't fit in 64 bits", spelling);
+        let value = i128::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow => too_big(),
+            _ => format!("{} has a digit that isn't base {}", spelling, radix),
+        })?;
+        if value > u64::MAX as i128 {
+            return Err(too_big());
+        }
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Reads a quoted literal, resolving the same escapes C++ has: `\n`, `\t`, `\r`, `\a`, `\b`, `\f`, `\v`,
+    /// `\\`, `\'`, `\"`, `\?`, octal `\0` to `\377`, `\xFF`, `\u00E9`, and `\U0001F600`.
+    fn parse_literal(val: &Pair<'_, Rule>) -> Result<Literal, MohoError> {
+        let raw = val.as_str();
+        let error =
+            |message: String| MohoError::spanned(ErrorCode::InvalidValue, message, val.as_span());
+
+        let mut text = String::new();
+        let mut chars = raw[1..raw.len() - 1].chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+
+            let escape = chars.next().unwrap_or_default();
+            let simple = match escape {
+                'n' => Some('\n'),
+                't' => Some('\t'),
+                'r' => Some('\r'),
+                'a' => Some('\u{7}'),
+                'b' => Some('\u{8}'),
+                'f' => Some('\u{c}'),
+                'v' => Some('\u{b}'),
+                '\\' | '\'' | '"' | '?' => Some(escape),
+                _ => None,
+            };
+            if let Some(c) = simple {
+                text.push(c);
+                continue;
+            }
+
+            // octal takes up to three digits, `\x` as many as there are, `\u` and `\U` exactly four and eight
+            let (radix, min, max) = match escape {
+                '0'..='7' => (8, 1, 3),
+                'x' => (16, 1, usize::MAX),
+                'u' => (16, 4, 4),
+                'U' => (16, 8, 8),
+                _ => return Err(error(format!("unknown escape `\\{}` in {}", escape, raw))),
+            };
+
+            let mut code = String::new();
+            if radix == 8 {
+                code.push(escape);
+            }
+            while code.len() < max && chars.peek().is_some_and(|c| c.is_digit(radix)) {
+                code.extend(chars.next());
+            }
+            if code.len() < min {
+                return Err(error(format!(
+                    "`\\{}` needs {} hex digits in {}",
+                    escape, min, raw
+                )));
+            }
+
+            // an octal escape is a single byte in C++, like `\xFF`
+            if radix == 8 && u32::from_str_radix(&code, 8).is_ok_and(|value| value > 0o377) {
+                return Err(error(format!(
+                    "`\\{}` in {} is more than a byte; octal escapes go up to `\\377`",
+                    code, raw
+                )));
+            }
+
+            match u32::from_str_radix(&code, radix)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(c) => text.push(c),
+                None => {
+                    let escaped = if radix == 8 {
+                        code
+                    } else {
+                        format!("{}{}", escape, code)
+                    };
+                    return Err(error(format!(
+                        "`\\{}` isn't a character in {}",
+                        escaped, raw
+                    )));
+                }
+            }
+        }
+
+        Ok(Literal {
+            raw: raw.to_string(),
+            text,
+        })
+    }
+
+    fn parse_argument<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Argument, MohoError> {
         let mut arg = Argument {
             properties: vec![],
             name: "".to_string(),
@@ -663,6 +1510,7 @@ impl MohoParser {
 
         for it in iter {
             match it.as_rule() {
+                Rule::doc => return Err(Self::doc_in_arguments(it)),
                 Rule::properties => {
                     arg.properties = Self::parse_properties(it)?;
                 }
@@ -680,7 +1528,8 @@ impl MohoParser {
                     arg.value = Some(Self::parse_value(&mut it.into_inner())?);
                 }
                 _ => {
-                    return Err(MohoError::ParseArgumentError(
+                    return Err(MohoError::spanned(
+                        ErrorCode::InvalidArgument,
                         format!("Cannot parse argument: {:?}", it.as_str()),
                         it.as_span(),
                     ));
@@ -691,16 +1540,33 @@ impl MohoParser {
         Ok(arg)
     }
 
-    fn parse_arguments<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Vec<Argument>, MohoError<'a>> {
+    /// Unreal documents arguments with `@param` in the method's doc comment, so that's where they go.
+    fn doc_in_arguments(doc: Pair<'_, Rule>) -> MohoError {
+        MohoError::spanned(
+            ErrorCode::InvalidArgument,
+            "doc comments can't go in an argument list; describe the argument with `@param` in the method's doc comment",
+            doc.as_span(),
+        )
+    }
+
+    fn parse_arguments<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Vec<Argument>, MohoError> {
         let mut result = vec![];
         for arg in iter {
-            result.push(Self::parse_argument(&mut arg.into_inner())?);
+            if matches!(arg.as_rule(), Rule::doc) {
+                return Err(Self::doc_in_arguments(arg));
+            }
+
+            let arg = Self::parse_argument(&mut arg.into_inner())?;
+            // `()` matches as a single argument with nothing in it
+            if !(arg.name.is_empty() && arg.typ.is_empty()) {
+                result.push(arg);
+            }
         }
 
         Ok(result)
     }
 
-    fn parse_value<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Value, MohoError<'a>> {
+    fn parse_value<'a>(iter: &mut Pairs<'a, Rule>) -> Result<Value, MohoError> {
         if let Some(value) = iter.next() {
             Self::match_value(value)
         } else {
@@ -708,28 +1574,64 @@ impl MohoParser {
         }
     }
 
-    fn parse_declaration(decl: Pair<'_, Rule>) -> Result<Declaration, MohoError> {
+    fn parse_declaration(
+        decl: Pair<'_, Rule>,
+        access: Option<Access>,
+        errors: &mut Vec<MohoError>,
+    ) -> Result<Declaration, MohoError> {
+        if matches!(decl.as_rule(), Rule::bad_declaration) {
+            return Err(Self::reparse_error(decl, Rule::strict_declaration));
+        }
+
         let span = decl.as_span();
         let mut result = Block {
             properties: vec![],
             inner: vec![],
         };
 
+        let mut doc = None;
         for pair in decl.into_inner() {
-            if matches!(pair.as_rule(), Rule::properties) {
+            if matches!(pair.as_rule(), Rule::doc) {
+                doc = Some(Self::parse_doc(pair));
+            } else if matches!(pair.as_rule(), Rule::properties) {
                 result
                     .properties
                     .extend(MohoParser::parse_properties(pair)?);
             } else if matches!(pair.as_rule(), Rule::field_decl) {
-                result.inner.push(Self::parse_field(pair)?);
+                result
+                    .inner
+                    .push(Self::parse_field(pair, access, doc.take())?);
             } else if matches!(pair.as_rule(), Rule::method_decl) {
-                result.inner.push(Self::parse_method(pair)?);
+                result
+                    .inner
+                    .push(Self::parse_method(pair, access, doc.take())?);
             } else if matches!(pair.as_rule(), Rule::block_decl) {
+                // a label inside a block only lasts until the end of the block
+                let mut access = access;
                 for inner in pair.into_inner() {
-                    result.inner.push(Self::parse_declaration(inner)?);
+                    if matches!(inner.as_rule(), Rule::access_label) {
+                        access = Access::parse(inner.as_str());
+                        continue;
+                    }
+                    // a doc comment in the block that isn't above anything
+                    if matches!(inner.as_rule(), Rule::doc) {
+                        errors.push(Self::dangling_doc(inner));
+                        continue;
+                    }
+                    match Self::parse_declaration(inner, access, errors) {
+                        Ok(mut decl) => {
+                            // a doc comment above a block documents everything in it
+                            if let Some(doc) = &doc {
+                                decl.inherit_doc(doc);
+                            }
+                            result.inner.push(decl)
+                        }
+                        Err(e) => errors.push(e),
+                    }
                 }
             } else {
-                return Err(MohoError::DeclarationError(
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidDeclaration,
                     format!("Unexpected token {:?} in block.", pair),
                     span,
                 ));
@@ -739,7 +1641,11 @@ impl MohoParser {
         Ok(Declaration::Block(result))
     }
 
-    fn parse_field(field: Pair<'_, Rule>) -> Result<Declaration, MohoError> {
+    fn parse_field(
+        field: Pair<'_, Rule>,
+        access: Option<Access>,
+        doc: Option<String>,
+    ) -> Result<Declaration, MohoError> {
         let span = field.as_span();
         let mut result = Field {
             is_static: false,
@@ -747,6 +1653,8 @@ impl MohoParser {
             name: "".into(),
             typ: Type::Void,
             value: None,
+            access,
+            doc,
         };
 
         for pair in field.into_inner() {
@@ -762,8 +1670,11 @@ impl MohoParser {
                 result.name = pair.as_str().trim().to_string();
             } else if matches!(pair.as_rule(), Rule::value) {
                 result.value = Some(Self::parse_value(&mut pair.into_inner())?);
+            } else if matches!(pair.as_rule(), Rule::declaration_end) {
+                Self::add_trailing_doc(&mut result.doc, pair);
             } else {
-                return Err(MohoError::ParseFieldError(
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidField,
                     format!("Cannot parse {:?} in field.", pair),
                     span,
                 ));
@@ -773,7 +1684,11 @@ impl MohoParser {
         Ok(Declaration::Field(result))
     }
 
-    fn parse_method(method: Pair<'_, Rule>) -> Result<Declaration, MohoError> {
+    fn parse_method(
+        method: Pair<'_, Rule>,
+        access: Option<Access>,
+        doc: Option<String>,
+    ) -> Result<Declaration, MohoError> {
         let span = method.as_span();
         let mut result = Method {
             is_static: false,
@@ -781,6 +1696,9 @@ impl MohoParser {
             name: "".into(),
             returns: "".into(),
             arguments: vec![],
+            access,
+            doc,
+            location: Some(Location::from_span(&span)),
         };
 
         for pair in method.into_inner() {
@@ -801,8 +1719,11 @@ impl MohoParser {
                 result.returns = typ;
             } else if matches!(pair.as_rule(), Rule::arguments) {
                 result.arguments = Self::parse_arguments(&mut pair.into_inner())?;
+            } else if matches!(pair.as_rule(), Rule::declaration_end) {
+                Self::add_trailing_doc(&mut result.doc, pair);
             } else {
-                return Err(MohoError::ParseFieldError(
+                return Err(MohoError::spanned(
+                    ErrorCode::InvalidField,
                     format!("Cannot parse {:?} in field.", pair),
                     span,
                 ));
@@ -824,6 +1745,7 @@ impl MohoParser {
             Rule::identifier if pair.as_str() == "long" => Ok(Type::Integer),
             Rule::identifier if pair.as_str() == "string" => Ok(Type::String),
             Rule::identifier => Ok(Type::Class(pair.as_str().to_string())),
+            Rule::generic_type => Self::parse_generic_type(pair),
 
             _ => {
                 if let Some(typ) = pair.into_inner().next() {
@@ -853,16 +1775,17 @@ impl MohoParser {
                         Rule::identifier if typ.as_str() == "long" => Ok(Type::Integer),
                         Rule::identifier if typ.as_str() == "string" => Ok(Type::String),
                         Rule::identifier => Ok(Type::Class(typ.as_str().to_string())),
+                        Rule::generic_type => Self::parse_generic_type(typ),
 
-                        _ => {
-                            return Err(MohoError::ParseTypeError(
-                                format!("Unknown type {:?}.", str),
-                                span,
-                            ));
-                        }
+                        _ => Err(MohoError::spanned(
+                            ErrorCode::UnknownType,
+                            format!("Unknown type {:?}.", str),
+                            span,
+                        )),
                     }
                 } else {
-                    Err(MohoError::ParseTypeError(
+                    Err(MohoError::spanned(
+                        ErrorCode::UnknownType,
                         format!("Parsing type {:?} failed.", str),
                         span,
                     ))
@@ -870,4 +1793,525 @@ impl MohoParser {
             }
         }
     }
+
+    fn parse_generic_type(pair: Pair<'_, Rule>) -> Result<Type, MohoError> {
+        let span = pair.as_span();
+        let mut inner = pair.into_inner();
+        let Some(name) = inner.next() else {
+            return Err(MohoError::spanned(
+                ErrorCode::UnknownType,
+                "Generic type name missing.",
+                span,
+            ));
+        };
+
+        let args = inner.map(Self::parse_type).collect::<Result<Vec<_>, _>>()?;
+        Ok(Type::Generic(name.as_str().to_string(), args))
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::{
+        error::ErrorCode,
+        parser::{MohoParser, Type, Value},
+    };
+
+    fn field_types(input: &str) -> Vec<Type> {
+        let unit = MohoParser::apply(input).expect("should parse");
+        unit.classes[0]
+            .inner
+            .clone()
+            .fields()
+            .into_iter()
+            .map(|f| f.typ)
+            .collect()
+    }
+
+    #[test]
+    fn parse_generic_types() {
+        let mut types = field_types(
+            "class A { TMap<FName, int32> Scores; TArray<TArray<AActor*>> Grid; TSubclassOf<AActor>* Spawn; }",
+        );
+
+        assert_eq!(types[0].name(), "TMap");
+        assert_eq!(types[0].args().len(), 2);
+        assert_eq!(types[0].arg(1).unwrap().cpp(), "int32");
+        assert_eq!(types[0].cpp(), "TMap<FName, int32>");
+        assert_eq!(types[1].cpp(), "TArray<TArray<TObjectPtr<AActor>>>");
+        assert_eq!(types[2].cpp(), "TObjectPtr<TSubclassOf<AActor>>");
+        assert!(types[2].arg(0).is_err());
+    }
+
+    #[test]
+    fn parse_net_methods() {
+        let unit = MohoParser::apply(
+            "class A\n{\n    [Server, Reliable]\n    void Fire();\n\n    int Score(int32 Round = 1);\n}",
+        )
+        .expect("should parse");
+        let mut methods = unit.classes[0].inner.clone().methods();
+
+        assert!(methods[0].is_net());
+        assert!(methods[0].arguments.is_empty());
+        assert_eq!(methods[0].location.as_ref().unwrap().line, 4);
+        assert!(!methods[1].is_net());
+        assert_eq!(methods[1].arguments_as_str(), "int32 Round");
+        assert_eq!(methods[1].arguments_with_defaults(), "int32 Round = 1");
+        assert_eq!(methods[1].argument_types(), "int32");
+    }
+
+    #[test]
+    fn parse_access_labels() {
+        let unit = MohoParser::apply(
+            r#"
+class A
+{
+    int Default;
+    void Open();
+
+private:
+    int Hidden;
+    [#Access=public]
+    int Shown;
+
+    [EditAnywhere]
+    {
+    protected:
+        int Inner;
+    }
+    int After;
+    void Close();
+}
+"#,
+        )
+        .expect("should parse");
+        let fields = unit.classes[0].inner.clone().fields();
+        let access = fields
+            .into_iter()
+            .map(|mut f| (f.name.clone(), f.get_access()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            access,
+            vec![
+                ("Default".into(), "protected".into()),
+                ("Hidden".into(), "private".into()),
+                ("Shown".into(), "public".into()),
+                ("Inner".into(), "protected".into()),
+                ("After".into(), "private".into()),
+            ]
+        );
+
+        let methods = unit.classes[0].inner.clone().methods();
+        assert_eq!(methods[0].clone().get_access(), "public");
+        assert_eq!(methods[1].clone().get_access(), "private");
+
+        // the innermost property wins, whether it's on a block or the declaration
+        let unit = MohoParser::apply(
+            r#"
+class A
+{
+    [#Access=public, #ClampMin=0]
+    {
+        [#Access=private, #ClampMin=5]
+        int X;
+
+        [#Access=protected]
+        {
+            int Y;
+        }
+
+        int Z;
+    }
+}
+"#,
+        )
+        .expect("should parse");
+        let mut fields = unit.classes[0].inner.clone().fields();
+        let access = fields
+            .clone()
+            .into_iter()
+            .map(|mut f| f.get_access())
+            .collect::<Vec<_>>();
+        assert_eq!(access, vec!["private", "protected", "public"]);
+        assert_eq!(fields[0].property("ClampMin"), "5");
+        let meta = fields[0]
+            .get_meta_properties()
+            .into_iter()
+            .map(|p| p.value.unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(meta, vec!["private", "5"]);
+        assert_eq!(fields[2].property("ClampMin"), "0");
+
+        let (_, errors) = MohoParser::apply_recovering("class A { [#Access=\"secret\"] int X; }");
+        assert_eq!(errors.len(), 1);
+        let (_, errors) = MohoParser::apply_recovering("class A { [#Access=secret] int X; }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::InvalidProperty);
+    }
+
+    #[test]
+    fn parse_comments() {
+        let unit = MohoParser::apply(
+            r#"
+// not a doc comment
+/// An enemy.
+/// Spawns at night.
+class AEnemy : AActor
+{
+    /* health */ int Health; // in points
+
+    /// How fast it walks
+    [EditAnywhere]
+    float Speed;
+
+    //// not a doc comment either
+    void Attack(/* who */ AActor* Target);
+
+    /// Runs away
+    void Flee();
+
+    /// Where it's headed
+    [VisibleAnywhere]
+    {
+        FVector Target;
+
+        /// How far it sees
+        float Range;
+    }
+}
+
+enum EMood
+{
+    /// Not angry
+    Calm,
+    Angry,
+}
+"#,
+        )
+        .expect("should parse");
+
+        let class = &unit.classes[0];
+        assert_eq!(class.doc.as_deref(), Some("An enemy.\nSpawns at night."));
+
+        let fields = class.inner.clone().fields();
+        assert_eq!(fields[0].doc, None);
+        assert_eq!(fields[1].doc.as_deref(), Some("How fast it walks"));
+        assert_eq!(fields[2].doc.as_deref(), Some("Where it's headed"));
+        assert_eq!(fields[3].doc.as_deref(), Some("How far it sees"));
+
+        let methods = class.inner.clone().methods();
+        assert_eq!(methods[0].doc, None);
+        assert_eq!(methods[0].arguments.len(), 1);
+        assert_eq!(methods[1].doc.as_deref(), Some("Runs away"));
+
+        let entries = &unit.enums[0].entries;
+        assert_eq!(entries[0].doc.as_deref(), Some("Not angry"));
+        assert_eq!(entries[1].doc, None);
+    }
+
+    #[test]
+    fn trailing_and_dangling_docs() {
+        pest::set_error_detail(true);
+        let unit = MohoParser::apply(
+            r#"
+class AEnemy : AActor
+{
+    int32 Health; /// in points
+    /// How fast it walks
+    float Speed;
+
+    /// Strikes back
+    void Attack(); /// when it's hit
+}
+
+enum EMood
+{
+    Calm, /// not angry
+    Angry, /// very
+    Furious
+}
+"#,
+        )
+        .expect("should parse");
+
+        let class = &unit.classes[0];
+        let fields = class.inner.clone().fields();
+        assert_eq!(fields[0].doc.as_deref(), Some("in points"));
+        assert_eq!(fields[1].doc.as_deref(), Some("How fast it walks"));
+        let methods = class.inner.clone().methods();
+        assert_eq!(
+            methods[0].doc.as_deref(),
+            Some("Strikes back\nwhen it's hit")
+        );
+
+        let entries = &unit.enums[0].entries;
+        assert_eq!(entries[0].doc.as_deref(), Some("not angry"));
+        assert_eq!(entries[1].doc.as_deref(), Some("very"));
+        assert_eq!(entries[2].doc, None);
+
+        // a doc comment above nothing, at the end of a class, a block, or the file, or in an argument list
+        for (source, at) in [
+            ("class A\n{\n    int X;\n    /// above nothing\n}\n", (4, 5)),
+            (
+                "class A\n{\n    {\n        int X;\n        /// above nothing\n    }\n}\n",
+                (5, 9),
+            ),
+            (
+                "class A\n{\n    /// above a label\npublic:\n    int X;\n}\n",
+                (3, 5),
+            ),
+            ("class A\n{\n}\n/// above nothing\n", (4, 1)),
+            ("enum E\n{\n    X\n    /// above nothing\n}\n", (4, 5)),
+            (
+                "class A\n{\n    void F(/// the target\n    AActor* T);\n}\n",
+                (3, 12),
+            ),
+            (
+                "class A\n{\n    void F(AActor* T /// the target\n    );\n}\n",
+                (3, 22),
+            ),
+        ] {
+            let (_, errors) = MohoParser::apply_recovering(source);
+            let locations = errors
+                .iter()
+                .map(|e| e.location.as_ref().map(|l| (l.line, l.col)))
+                .collect::<Vec<_>>();
+            assert_eq!(locations, vec![Some(at)], "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_identifier_values() {
+        let unit = MohoParser::apply(
+            r#"
+class A
+{
+    [ReplicatedUsing=OnRep_Health, Condition=COND_OwnerOnly]
+    float Health;
+
+    EMode Mode = EMode::Fast;
+    bool trueValue = true;
+}
+"#,
+        )
+        .expect("should parse");
+        let mut fields = unit.classes[0].inner.clone().fields();
+
+        assert_eq!(fields[0].property("ReplicatedUsing"), "OnRep_Health");
+        assert_eq!(fields[0].property("Condition"), "COND_OwnerOnly");
+        assert_eq!(
+            fields[1].value,
+            Some(Value::Identifier("EMode::Fast".into()))
+        );
+        assert_eq!(fields[2].name, "trueValue");
+        assert_eq!(fields[2].value, Some(Value::Bool(true)));
+    }
+
+    #[test]
+    fn parse_literals() {
+        let unit = MohoParser::apply(
+            r#"
+class A
+{
+    string Greeting = "say \"hi\"\n\\ é";
+    char Letter = 'a';
+    char Quote = '\'';
+    char Tab = '\t';
+}
+"#,
+        )
+        .expect("should parse");
+        let mut values = unit.classes[0]
+            .inner
+            .clone()
+            .fields()
+            .into_iter()
+            .map(|f| f.value.expect("every field has a value"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(values[0].text(), "say \"hi\"\n\\ \u{e9}");
+        assert_eq!(values[0].raw(), r#""say \"hi\"\n\\ é""#);
+        assert_eq!(values[0].as_cpp(), r#"TEXT("say \"hi\"\n\\ é")"#);
+        assert_eq!(values[0].as_cpp_for(Type::String), values[0].as_cpp());
+        assert_eq!(
+            values[0].as_cpp_for(Type::Pointer(Box::new(Type::Char), 1)),
+            r#""say \"hi\"\n\\ é""#
+        );
+        assert_eq!(
+            values[0].as_cpp_for(Type::Class("FName".into())),
+            values[0].as_cpp()
+        );
+        assert_eq!(values[1].text(), "a");
+        assert_eq!(values[1].as_cpp(), "'a'");
+        assert_eq!(values[2].text(), "'");
+        assert_eq!(values[3].text(), "\t");
+
+        let unit = MohoParser::apply(
+            r#"
+class A
+{
+    string Bell = "\a\b\f\v";
+    string Octal = "\101\012\0";
+    string Short = "\7x\1234";
+    string Hex = "\x41\x7e";
+    string Unicode = "\u00E9\U0001F600";
+    string Simple = "\?\\";
+}
+"#,
+        )
+        .expect("should parse");
+        let mut values = unit.classes[0]
+            .inner
+            .clone()
+            .fields()
+            .into_iter()
+            .map(|f| f.value.expect("every field has a value"))
+            .collect::<Vec<_>>();
+        assert_eq!(values[0].text(), "\u{7}\u{8}\u{c}\u{b}");
+        assert_eq!(values[1].text(), "A\n\0");
+        assert_eq!(values[2].text(), "\u{7}xS4");
+        assert_eq!(values[3].text(), "A~");
+        assert_eq!(values[4].text(), "\u{e9}\u{1F600}");
+        assert_eq!(values[5].text(), "?\\");
+
+        for bad in [r#""\q""#, r#""\u12""#, r#""\x""#, r#""\8""#, "'ab'", "''"] {
+            let err = MohoParser::apply(&format!("class A {{ string X = {}; }}", bad))
+                .expect_err("should not parse");
+            assert_eq!(err.code, ErrorCode::InvalidValue, "{}", bad);
+        }
+
+        // an octal escape is one byte, so `\377` is the last one
+        let err =
+            MohoParser::apply(r#"class A { string X = "\400"; }"#).expect_err("should not parse");
+        assert_eq!(err.code, ErrorCode::InvalidValue);
+        assert!(err.message.contains("`\\400`"));
+        assert!(MohoParser::apply(r#"class A { string X = "\377\1234"; }"#).is_ok());
+    }
+
+    #[test]
+    fn parse_numbers() {
+        let unit = MohoParser::apply(
+            r#"
+enum ENumbers : int64
+{
+    A = -1,
+    B = 0xFF,
+    C = 017,
+    D = 0b1010,
+    E = 1'000'000,
+    F = 1_000,
+    G = 100u,
+    H = 0xFFFF'FFFF'FFFF'FFFFull,
+}
+"#,
+        )
+        .expect("should parse");
+        let values = unit.enums[0]
+            .entries
+            .iter()
+            .map(|e| match e.value.clone() {
+                Some(Value::Integer(value, _)) => value,
+                other => panic!("{:?} is not an integer", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![-1, 255, 15, 10, 1_000_000, 1_000, 100, u64::MAX as i128]
+        );
+
+        let mut value = unit.enums[0].entries[5].clone().get_value();
+        assert_eq!(value.raw(), "1_000");
+        assert_eq!(value.as_cpp(), "1'000");
+
+        let unit =
+            MohoParser::apply("class A { float X = 1.0f; double Y = -.5e-3; float Z = 2.; }")
+                .expect("should parse");
+        let mut values = unit.classes[0]
+            .inner
+            .clone()
+            .fields()
+            .into_iter()
+            .map(|f| f.value.expect("every field has a value"))
+            .collect::<Vec<_>>();
+        assert_eq!(values[0], Value::Float(1.0, "1.0f".into()));
+        assert_eq!(values[0].as_cpp(), "1.0f");
+        assert_eq!(values[1], Value::Float(-0.0005, "-.5e-3".into()));
+        assert_eq!(values[2].as_cpp(), "2.");
+
+        let err = MohoParser::apply("enum E { A = 0x1'0000'0000'0000'0000 }")
+            .expect_err("should not fit");
+        assert_eq!(err.code, ErrorCode::InvalidValue);
+        assert!(err.message.contains("doesn't fit in 64 bits"));
+
+        // too big even for the 128 bits it's read in, and digits the base doesn't have
+        assert_eq!(
+            MohoParser::parse_integer(&format!("0x{}", "F".repeat(40))),
+            Err(format!("0x{} doesn't fit in 64 bits", "F".repeat(40)))
+        );
+        assert_eq!(
+            MohoParser::parse_integer("0b102"),
+            Err("0b102 has a digit that isn't base 2".into())
+        );
+        assert_eq!(MohoParser::parse_integer("-0x10"), Ok(-16));
+    }
+
+    #[test]
+    fn recover_from_errors() {
+        pest::set_error_detail(true);
+        let input = "
+class ABroken : AActor
+{
+    float Health = 10
+    int X;
+
+    [EditAnywhere]
+    {
+        int 3y;
+        bool Fine;
+    }
+}
+
+class AMissingBrace : AActor
+    int x;
+}
+
+class AGood : AActor
+{
+    int Y;
+}
+
+enum EGood { A, B }
+";
+        let (unit, errors) = MohoParser::apply_recovering(input);
+
+        let lines = errors
+            .iter()
+            .map(|e| e.location.as_ref().unwrap().line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![5, 9, 15]);
+        assert!(errors.iter().all(|e| e.code == ErrorCode::Syntax));
+
+        // only the classes without errors make it into the translation unit
+        let names = unit
+            .classes
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["AGood"]);
+        assert_eq!(unit.enums.len(), 1);
+
+        assert!(MohoParser::apply(input).is_err());
+    }
+
+    #[test]
+    fn syntax_errors_leave_out_comments() {
+        pest::set_error_detail(true);
+        let (_, errors) = MohoParser::apply_recovering("class AX : AActor\n{\n    int X\n}\n");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location.as_ref().unwrap().line, 4);
+        assert!(errors[0].message.contains("`;`"));
+        assert!(!errors[0].message.contains("`//`"));
+        assert!(!errors[0].message.contains("`/*`"));
+    }
 }
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+const BEGIN: &str = "// moho:begin user ";
+const END: &str = "// moho:end";
+
+/// Generated output with the hand-written regions of the previous file carried over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedOutput {
+    pub text: String,
+    /// Regions that were in the previous file but aren't in the template anymore; their content is dropped.
+    pub orphans: Vec<String>,
+}
+
+fn region_name(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(BEGIN)
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+}
+
+fn is_region_end(line: &str) -> bool {
+    line.trim() == END
+}
+
+/// Every terminated `// moho:begin user Name` ... `// moho:end` region, in order, with the lines between the markers.
+pub fn user_regions(text: &str) -> Vec<(String, String)> {
+    let mut regions = vec![];
+    let mut current: Option<(String, String)> = None;
+
+    for line in text.split_inclusive('\n') {
+        if let Some((name, content)) = current.as_mut() {
+            if is_region_end(line) {
+                regions.push((std::mem::take(name), std::mem::take(content)));
+                current = None;
+            } else {
+                content.push_str(line);
+            }
+        } else if let Some(name) = region_name(line) {
+            current = Some((name.to_string(), String::new()));
+        }
+    }
+
+    regions
+}
+
+/// Replaces the content of every user region in `generated` with the same region from `existing`, if it has one.
+pub fn merge_user_regions(existing: &str, generated: &str) -> MergedOutput {
+    let mut previous = user_regions(existing)
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let mut text = String::new();
+    let mut lines = generated.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        text.push_str(line);
+
+        let Some(name) = region_name(line) else {
+            continue;
+        };
+
+        // keep the template's default content around in case the previous file didn't have this region
+        let mut default = String::new();
+        let mut end = None;
+        for line in lines.by_ref() {
+            if is_region_end(line) {
+                end = Some(line);
+                break;
+            }
+            default.push_str(line);
+        }
+
+        text.push_str(&previous.remove(name).unwrap_or(default));
+        if let Some(end) = end {
+            text.push_str(end);
+        }
+    }
+
+    let mut orphans = previous.into_keys().collect::<Vec<_>>();
+    orphans.sort();
+
+    MergedOutput { text, orphans }
+}
+
+#[cfg(test)]
+mod regions_tests {
+    use crate::regions::merge_user_regions;
+
+    #[test]
+    fn carry_user_regions_over() {
+        let existing = "\
+void A::BeginPlay()
+{
+    // moho:begin user BeginPlay
+    Spawn();
+    // moho:end
+}
+// moho:begin user Removed
+int Old;
+// moho:end
+";
+        let generated = "\
+void A::BeginPlay()
+{
+    Super::BeginPlay();
+    // moho:begin user BeginPlay
+    // moho:end
+}
+// moho:begin user Tick
+    // default
+// moho:end
+";
+
+        let merged = merge_user_regions(existing, generated);
+        assert_eq!(
+            merged.text,
+            "\
+void A::BeginPlay()
+{
+    Super::BeginPlay();
+    // moho:begin user BeginPlay
+    Spawn();
+    // moho:end
+}
+// moho:begin user Tick
+    // default
+// moho:end
+"
+        );
+        assert_eq!(merged.orphans, vec!["Removed".to_string()]);
+    }
+}
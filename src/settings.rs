@@ -0,0 +1,331 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::{
+    defaults::builtin_file,
+    error::{ErrorCode, Location, MohoError},
+    CmdArguments,
+};
+
+pub const CONFIG_FILE: &str = "moho.toml";
+
+/// Scripts and templates in this folder at the project root override everything else.
+pub const PROJECT_DIR: &str = ".moho";
+
+/// What a project's `moho.toml` can set; every key is optional and paths are relative to the file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub search_path: Vec<PathBuf>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub output: BTreeMap<PathBuf, PathBuf>,
+    pub default_script: Option<String>,
+    pub field_prefix: Option<String>,
+    /// Headers for types moho can't find on its own, e.g. `FGameplayTag = "GameplayTagContainer.h"`.
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Where a script or template was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Found {
+    File(PathBuf),
+    /// One of the files moho ships with, by name.
+    Builtin(String, &'static str),
+}
+
+impl Found {
+    /// The file's path, or `<builtin>/name` for a built-in one.
+    pub fn path(&self) -> PathBuf {
+        match self {
+            Found::File(path) => path.clone(),
+            Found::Builtin(name, _) => Path::new("<builtin>").join(name),
+        }
+    }
+
+    pub fn read(&self) -> std::io::Result<String> {
+        match self {
+            Found::File(path) => fs::read_to_string(path),
+            Found::Builtin(_, content) => Ok(content.to_string()),
+        }
+    }
+}
+
+/// The directories scripts and templates are looked up in, first match wins, with the built-in files after them.
+#[derive(Debug, Clone, Default)]
+pub struct SearchPath {
+    pub dirs: Vec<PathBuf>,
+}
+
+impl SearchPath {
+    /// Every place on disk `name` could be, whether it exists or not.
+    pub fn candidates(&self, name: &str) -> Vec<PathBuf> {
+        self.dirs.iter().map(|dir| dir.join(name)).collect()
+    }
+
+    pub fn find(&self, name: &str) -> Option<Found> {
+        self.candidates(name)
+            .into_iter()
+            .find(|f| f.is_file())
+            .map(Found::File)
+            .or_else(|| builtin_file(name).map(|content| Found::Builtin(name.into(), content)))
+    }
+}
+
+/// Generator settings: built-in defaults, overridden by the project's `moho.toml`, overridden by the command line.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Where `moho.toml` is, or the run path if there isn't one; globs and output mappings are relative to it.
+    pub root: PathBuf,
+    pub config_file: Option<PathBuf>,
+    pub search_path: SearchPath,
+    /// `None` means every .moho file is included.
+    pub include: Option<GlobSet>,
+    pub exclude: GlobSet,
+    /// Source directories and where their output goes, longest first.
+    pub output: Vec<(PathBuf, PathBuf)>,
+    pub default_script: String,
+    pub field_prefix: String,
+    /// Type to header, for the includes generated for every class and struct.
+    pub headers: BTreeMap<String, String>,
+}
+
+/// `path` relative to `base` (or the working directory, if `base` is empty), resolved as far as it exists.
+fn absolute(base: &Path, path: &Path) -> PathBuf {
+    let path = base.join(path);
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(&path))
+        .unwrap_or(path)
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, MohoError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` stays inside one directory, `**` crosses them
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                MohoError::new(
+                    ErrorCode::Config,
+                    format!("invalid glob `{}`: {}", pattern, e),
+                )
+            })?;
+        builder.add(glob);
+    }
+
+    builder
+        .build()
+        .map_err(|e| MohoError::new(ErrorCode::Config, e.to_string()))
+}
+
+fn read_config(path: &Path) -> Result<ProjectConfig, MohoError> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        MohoError::new(ErrorCode::Io, format!("cannot read file: {}", e)).in_file(path)
+    })?;
+
+    toml::from_str(&text).map_err(|e| {
+        let error = MohoError::new(ErrorCode::Config, e.message()).in_file(path);
+        match e.span() {
+            Some(span) => error.at(Location::from_range(&text, span)),
+            None => error,
+        }
+    })
+}
+
+impl Settings {
+    /// Finds `moho.toml` in the run path or any directory above it, unless `--config` names one.
+    pub fn load(run_path: &Path, args: &CmdArguments) -> Result<Settings, MohoError> {
+        let run_path = absolute(Path::new(""), run_path);
+        let config_file = match &args.config {
+            Some(file) => Some(absolute(Path::new(""), Path::new(file))),
+            None => run_path
+                .ancestors()
+                .map(|dir| dir.join(CONFIG_FILE))
+                .find(|file| file.is_file()),
+        };
+
+        let (root, config) = match &config_file {
+            Some(file) => (
+                file.parent().map(|p| p.to_path_buf()).unwrap_or_default(),
+                read_config(file)?,
+            ),
+            None => (run_path.clone(), ProjectConfig::default()),
+        };
+
+        let cwd = PathBuf::new();
+        // the project's own folder, then moho.toml's, then the user's; built-in files come after all of them
+        let user_dir = args.moho_path.clone().unwrap_or(crate::default_moho_path());
+        let mut search_path = vec![root.join(PROJECT_DIR)];
+        search_path.extend(config.search_path.iter().map(|dir| absolute(&root, dir)));
+        search_path.push(absolute(&cwd, Path::new(&user_dir)));
+
+        let mut seen = BTreeSet::new();
+        search_path.retain(|dir| seen.insert(dir.clone()));
+
+        let include = if args.include.is_empty() {
+            config.include
+        } else {
+            args.include.clone()
+        };
+        let exclude = if args.exclude.is_empty() {
+            config.exclude
+        } else {
+            args.exclude.clone()
+        };
+
+        let mut output = match &args.output_dir {
+            Some(dir) => vec![(root.clone(), absolute(&cwd, Path::new(dir)))],
+            None => config
+                .output
+                .iter()
+                .map(|(source, target)| (absolute(&root, source), absolute(&root, target)))
+                .collect(),
+        };
+        output.sort_by_key(|(source, _)| std::cmp::Reverse(source.components().count()));
+
+        Ok(Settings {
+            search_path: SearchPath { dirs: search_path },
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(&include)?)
+            },
+            exclude: glob_set(&exclude)?,
+            output,
+            default_script: args
+                .default_script
+                .clone()
+                .or(config.default_script)
+                .unwrap_or("base.rhai".into()),
+            field_prefix: args
+                .field_prefix
+                .clone()
+                .or(config.field_prefix)
+                .unwrap_or("m_".into()),
+            headers: config.headers,
+            root,
+            config_file,
+        })
+    }
+
+    /// Whether a .moho file should be generated, judged by its path relative to the root.
+    pub fn includes(&self, path: &Path) -> bool {
+        if !path.extension().map(|e| e == "moho").unwrap_or_default() {
+            return false;
+        }
+
+        let path = absolute(Path::new(""), path);
+        let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+        self.include
+            .as_ref()
+            .map(|include| include.is_match(relative))
+            .unwrap_or(true)
+            && !self.exclude.is_match(relative)
+    }
+
+    /// Where the files generated from a .moho file in `source_dir` go; subdirectories of a mapped directory are kept.
+    pub fn output_dir(&self, source_dir: &Path) -> PathBuf {
+        let source_dir = absolute(Path::new(""), source_dir);
+        self.output
+            .iter()
+            .find_map(|(source, target)| {
+                source_dir
+                    .strip_prefix(source)
+                    .ok()
+                    .map(|rest| target.join(rest))
+            })
+            .unwrap_or(source_dir)
+    }
+}
+
+#[cfg(test)]
+mod settings_tests {
+    use std::fs;
+
+    use crate::{
+        settings::{Found, Settings},
+        CmdArguments,
+    };
+
+    #[test]
+    fn project_config_and_overrides() {
+        let root = std::env::temp_dir().join(format!("moho-settings-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Source/Moho/Weapons")).unwrap();
+        fs::write(
+            root.join("moho.toml"),
+            r#"
+search_path = ["Tools/Moho"]
+exclude = ["**/Intermediate/**"]
+default_script = "UObject.rhai"
+field_prefix = ""
+
+[output]
+"Source/Moho" = "Source/Game/Public"
+"#,
+        )
+        .unwrap();
+
+        fs::create_dir_all(root.join(".moho")).unwrap();
+        fs::write(root.join(".moho/field-name-template.txt"), "{{name}}").unwrap();
+
+        let args = CmdArguments {
+            moho_path: Some(root.join("User").to_str().unwrap().into()),
+            ..Default::default()
+        };
+        let settings = Settings::load(&root.join("Source/Moho"), &args).unwrap();
+        let root = root.canonicalize().unwrap();
+        assert_eq!(settings.root, root);
+        assert_eq!(
+            settings.search_path.dirs,
+            vec![
+                root.join(".moho"),
+                root.join("Tools/Moho"),
+                root.join("User")
+            ]
+        );
+        assert_eq!(
+            settings.search_path.find("field-name-template.txt"),
+            Some(Found::File(root.join(".moho/field-name-template.txt")))
+        );
+        assert!(matches!(
+            settings.search_path.find("UENUM.rhai"),
+            Some(Found::Builtin(..))
+        ));
+        assert_eq!(settings.search_path.find("missing.txt"), None);
+        assert_eq!(settings.default_script, "UObject.rhai");
+        assert_eq!(settings.field_prefix, "");
+        assert!(settings.includes(&root.join("Source/Moho/A.moho")));
+        assert!(!settings.includes(&root.join("Source/Intermediate/A.moho")));
+        assert!(!settings.includes(&root.join("Source/Moho/A.txt")));
+        assert_eq!(
+            settings.output_dir(&root.join("Source/Moho/Weapons")),
+            root.join("Source/Game/Public/Weapons")
+        );
+        assert_eq!(settings.output_dir(&root.join("Other")), root.join("Other"));
+
+        let args = CmdArguments {
+            moho_path: None,
+            field_prefix: Some("b".into()),
+            include: vec!["Source/Moho/*.moho".into()],
+            ..Default::default()
+        };
+        let settings = Settings::load(&root, &args).unwrap();
+        assert_eq!(settings.field_prefix, "b");
+        assert!(settings.includes(&root.join("Source/Moho/A.moho")));
+        assert!(!settings.includes(&root.join("Source/Moho/Weapons/A.moho")));
+
+        fs::write(root.join("moho.toml"), "field_prefix = 3\n").unwrap();
+        let error = Settings::load(&root, &args).unwrap_err();
+        assert_eq!(error.location.map(|l| (l.line, l.col)), Some((1, 16)));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use rhai::CustomType;
+
+use crate::{
+    error::{ErrorCode, MohoError},
+    hierarchy::engine_parent,
+    parser::{Block, Class, Enum, Field, MohoParser, Struct},
+};
+
+/// Every class, struct, and enum in the project, with the .moho file it's defined in.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    pub classes: BTreeMap<String, (Class, PathBuf)>,
+    pub structs: BTreeMap<String, (Struct, PathBuf)>,
+    pub enums: BTreeMap<String, (Enum, PathBuf)>,
+}
+
+impl SymbolTable {
+    /// Parses every file up front; syntax errors are left for `run_moho` to report, only names defined
+    /// twice are reported here, against the file that came second.
+    pub fn build(files: &[PathBuf]) -> (SymbolTable, Vec<MohoError>) {
+        let mut table = SymbolTable::default();
+        let mut errors = vec![];
+
+        for file in files {
+            let Ok(input) = fs::read_to_string(file) else {
+                continue;
+            };
+
+            let (translation_unit, _) = MohoParser::apply_recovering(&input);
+            // a duplicate is reported and skipped, so the first definition wins even across kinds
+            let mut is_new = |table: &SymbolTable, name: &str| match table.defined_in(name) {
+                Some(defined_in) => {
+                    errors.push(
+                        MohoError::new(
+                            ErrorCode::DuplicateDefinition,
+                            format!("`{}` is already defined in {}", name, defined_in.display()),
+                        )
+                        .in_file(file),
+                    );
+                    false
+                }
+                None => true,
+            };
+
+            for class in translation_unit.classes {
+                if is_new(&table, &class.name) {
+                    table
+                        .classes
+                        .insert(class.name.clone(), (class, file.clone()));
+                }
+            }
+
+            for structure in translation_unit.structs {
+                if is_new(&table, &structure.name) {
+                    table
+                        .structs
+                        .insert(structure.name.clone(), (structure, file.clone()));
+                }
+            }
+
+            for enumeration in translation_unit.enums {
+                if is_new(&table, &enumeration.name) {
+                    table
+                        .enums
+                        .insert(enumeration.name.clone(), (enumeration, file.clone()));
+                }
+            }
+        }
+
+        (table, errors)
+    }
+
+    pub fn defined_in(&self, name: &str) -> Option<&PathBuf> {
+        self.classes
+            .get(name)
+            .map(|(_, file)| file)
+            .or(self.structs.get(name).map(|(_, file)| file))
+            .or(self.enums.get(name).map(|(_, file)| file))
+    }
+
+    fn record(&self, name: &str) -> Option<(&Vec<String>, &Block)> {
+        self.classes
+            .get(name)
+            .map(|(class, _)| (&class.inherit, &class.inner))
+            .or(self
+                .structs
+                .get(name)
+                .map(|(structure, _)| (&structure.inherit, &structure.inner)))
+    }
+
+    /// The superclass of a class or struct defined in a .moho file.
+    pub fn parent(&self, name: &str) -> Option<&str> {
+        self.record(name)
+            .and_then(|(inherit, _)| inherit.first())
+            .map(|parent| parent.as_str())
+    }
+
+    /// The classes or structs `name` inherits from that are defined in .moho files, nearest first.
+    pub fn moho_ancestors(&self, name: &str) -> Vec<String> {
+        let mut ancestors: Vec<String> = vec![];
+        let mut current = self.parent(name);
+        while let Some(parent) = current {
+            if self.record(parent).is_none() || ancestors.iter().any(|a| a == parent) {
+                break;
+            }
+            ancestors.push(parent.to_string());
+            current = self.parent(parent);
+        }
+        ancestors
+    }
+
+    /// The fields of every .moho ancestor of `name`, starting from the one furthest up.
+    pub fn inherited_fields(&self, name: &str) -> Vec<Field> {
+        let mut ancestors = self.moho_ancestors(name);
+        ancestors.reverse();
+        ancestors
+            .iter()
+            .filter_map(|ancestor| self.record(ancestor))
+            .flat_map(|(_, inner)| inner.clone().fields())
+            .collect()
+    }
+
+    /// Whether `name` is `ancestor` or inherits from it, through .moho classes and then the engine class table.
+    pub fn derives_from(&self, name: &str, ancestor: &str) -> bool {
+        let mut seen: Vec<&str> = vec![];
+        let mut current = Some(name);
+        while let Some(class) = current {
+            if class == ancestor {
+                return true;
+            }
+            if seen.contains(&class) {
+                return false;
+            }
+            seen.push(class);
+            current = self.parent(class).or(engine_parent(class));
+        }
+        false
+    }
+
+    pub fn has_class(&self, name: &str) -> bool {
+        self.classes.contains_key(name)
+    }
+
+    pub fn has_struct(&self, name: &str) -> bool {
+        self.structs.contains_key(name)
+    }
+
+    pub fn has_enum(&self, name: &str) -> bool {
+        self.enums.contains_key(name)
+    }
+
+    pub fn get_class(&self, name: &str) -> rhai::Dynamic {
+        self.classes
+            .get(name)
+            .map(|(class, _)| rhai::Dynamic::from(class.clone()))
+            .unwrap_or(rhai::Dynamic::UNIT)
+    }
+}
+
+impl CustomType for SymbolTable {
+    fn build(mut builder: rhai::TypeBuilder<Self>) {
+        builder
+            .with_name("Project")
+            .with_fn("has_class", |t: &mut Self, name: &str| t.has_class(name))
+            .with_fn("has_struct", |t: &mut Self, name: &str| t.has_struct(name))
+            .with_fn("has_enum", |t: &mut Self, name: &str| t.has_enum(name))
+            .with_fn("class", |t: &mut Self, name: &str| t.get_class(name))
+            .with_fn(
+                "derives_from",
+                |t: &mut Self, name: &str, ancestor: &str| t.derives_from(name, ancestor),
+            )
+            .with_fn("inherited_fields", |t: &mut Self, name: &str| {
+                t.inherited_fields(name)
+            });
+    }
+}
+
+#[cfg(test)]
+mod symbols_tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{error::ErrorCode, symbols::SymbolTable};
+
+    #[test]
+    fn classes_across_files() {
+        let dir = std::env::temp_dir().join(format!("moho-symbols-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let files: Vec<PathBuf> = vec![dir.join("base.moho"), dir.join("goblin.moho")];
+        fs::write(
+            &files[0],
+            "class ABaseEnemy : AActor\n{\n    int Health;\n}\n\nclass AEnemy : ABaseEnemy\n{\n    float Speed;\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &files[1],
+            "class AGoblin : AEnemy\n{\n    int Gold;\n}\n\nenum ABaseEnemy\n{\n    A\n}\n",
+        )
+        .unwrap();
+
+        let (table, errors) = SymbolTable::build(&files);
+        assert_eq!(
+            table.moho_ancestors("AGoblin"),
+            vec!["AEnemy", "ABaseEnemy"]
+        );
+        assert_eq!(table.parent("ABaseEnemy"), Some("AActor"));
+        assert_eq!(
+            table
+                .inherited_fields("AGoblin")
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Health", "Speed"]
+        );
+        assert!(table.has_class("AGoblin"));
+        assert!(table.derives_from("AGoblin", "AActor"));
+        assert!(table.derives_from("AGoblin", "UObject"));
+        assert!(!table.derives_from("AGoblin", "UActorComponent"));
+        assert!(!table.has_enum("ABaseEnemy"));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::DuplicateDefinition);
+        assert_eq!(errors[0].file.as_ref(), Some(&files[1]));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use walkdir::WalkDir;
+
+use crate::{
+    diagnostics::Diagnostic,
+    output::{Dependencies, OutputSink},
+    relative_to_cwd, run_moho,
+    settings::Settings,
+    symbols::SymbolTable,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// When every file under `root` that passes `filter` was last modified, keyed by its canonical path.
+fn modified_times(root: &Path, filter: impl Fn(&Path) -> bool) -> BTreeMap<PathBuf, SystemTime> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && filter(entry.path()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            let path = entry.path().canonicalize().ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// Files that were created, modified, or deleted between two polls; a created or deleted script matters too,
+/// since a missing script may be what a file fell back from.
+fn changed(
+    before: &BTreeMap<PathBuf, SystemTime>,
+    after: &BTreeMap<PathBuf, SystemTime>,
+) -> BTreeSet<PathBuf> {
+    after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(*modified))
+        .map(|(path, _)| path)
+        .chain(before.keys().filter(|path| !after.contains_key(*path)))
+        .cloned()
+        .collect()
+}
+
+/// The .moho files to regenerate after `changed_files` changed: the .moho files among them that still exist, and
+/// every file that used one of them. Files redefining a name from another file are left out, like `generate` does.
+fn dirty_files(
+    changed_files: &BTreeSet<PathBuf>,
+    moho_files: &BTreeMap<PathBuf, SystemTime>,
+    dependencies: &BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    duplicates: &BTreeSet<PathBuf>,
+) -> BTreeSet<PathBuf> {
+    let used_changed = dependencies
+        .iter()
+        .filter(|(_, used)| changed_files.iter().any(|path| used.contains(path)))
+        .map(|(moho_file, _)| moho_file);
+
+    changed_files
+        .iter()
+        .chain(used_changed)
+        .filter(|path| moho_files.contains_key(*path) && !duplicates.contains(*path))
+        .cloned()
+        .collect()
+}
+
+/// Runs a single .moho file, printing its errors, and returns the config and .moho files it used.
+fn regenerate(path: &Path, settings: &Settings, symbols: &Arc<SymbolTable>) -> BTreeSet<PathBuf> {
+    let sink = OutputSink::disk();
+    let dependencies = Dependencies::default();
+
+    match run_moho(
+        path.to_path_buf(),
+        settings.clone(),
+        sink.clone(),
+        dependencies.clone(),
+        symbols.clone(),
+    ) {
+        Ok(()) => println!(
+            "Regenerated {}: {}",
+            relative_to_cwd(path).display(),
+            sink.summary()
+        ),
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{}", Diagnostic::new(error));
+            }
+            eprintln!("moho failed on {}", relative_to_cwd(path).display());
+        }
+    }
+
+    dependencies.files()
+}
+
+/// Regenerates every .moho file under `run_path` once, then keeps polling: a changed .moho file is regenerated
+/// along with the files inheriting from its classes, and a changed script or template regenerates every file that used it.
+pub fn watch(run_path: &Path, settings: &Settings) -> ! {
+    let config_times = || {
+        settings
+            .search_path
+            .dirs
+            .iter()
+            .flat_map(|dir| modified_times(dir, |_| true))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let mut moho_files = BTreeMap::new();
+    let mut config_files = config_times();
+    let mut dependencies: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut symbols = Arc::new(SymbolTable::default());
+    let mut duplicates = BTreeSet::new();
+
+    println!(
+        "Watching {} for changes, press Ctrl+C to stop",
+        run_path.display()
+    );
+
+    loop {
+        let current_moho_files = modified_times(run_path, |path| settings.includes(path));
+        let current_config_files = config_times();
+
+        let changed_moho_files = changed(&moho_files, &current_moho_files);
+        let changed_config_files = changed(&config_files, &current_config_files);
+
+        // the symbol table is rebuilt from scratch whenever any .moho file changes; a file that no longer
+        // redefines a name (because the other definition went away) is generated again
+        let mut changed_files = changed_moho_files;
+        if !changed_files.is_empty() {
+            let files = current_moho_files.keys().cloned().collect::<Vec<_>>();
+            let (table, errors) = SymbolTable::build(&files);
+            let current_duplicates = errors
+                .iter()
+                .filter_map(|error| error.file.clone())
+                .collect::<BTreeSet<_>>();
+            for error in errors {
+                eprintln!("{}", Diagnostic::new(error));
+            }
+            changed_files.extend(duplicates.difference(&current_duplicates).cloned());
+            duplicates = current_duplicates;
+            symbols = Arc::new(table);
+        }
+        changed_files.extend(changed_config_files);
+
+        dependencies.retain(|path, _| current_moho_files.contains_key(path));
+        let dirty = dirty_files(
+            &changed_files,
+            &current_moho_files,
+            &dependencies,
+            &duplicates,
+        );
+
+        moho_files = current_moho_files;
+        config_files = current_config_files;
+
+        for path in dirty {
+            let used = regenerate(&path, settings, &symbols);
+            dependencies.insert(path, used);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::watch::{changed, dirty_files};
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn files(entries: &[(&str, u64)]) -> BTreeMap<PathBuf, SystemTime> {
+        entries
+            .iter()
+            .map(|(path, seconds)| (PathBuf::from(path), at(*seconds)))
+            .collect()
+    }
+
+    fn paths(entries: &[&str]) -> BTreeSet<PathBuf> {
+        entries.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn created_modified_and_deleted_files() {
+        let before = files(&[("same.moho", 1), ("edited.moho", 1), ("deleted.moho", 1)]);
+        let after = files(&[("same.moho", 1), ("edited.moho", 2), ("created.moho", 2)]);
+
+        assert_eq!(
+            changed(&before, &after),
+            paths(&["created.moho", "deleted.moho", "edited.moho"])
+        );
+        assert!(changed(&after, &after).is_empty());
+        assert_eq!(
+            changed(&BTreeMap::new(), &after),
+            paths(&["created.moho", "edited.moho", "same.moho"])
+        );
+    }
+
+    #[test]
+    fn dependents_are_regenerated() {
+        let moho_files = files(&[
+            ("base.moho", 1),
+            ("goblin.moho", 1),
+            ("orc.moho", 1),
+            ("other.moho", 1),
+        ]);
+        let dependencies = BTreeMap::from([
+            (PathBuf::from("base.moho"), paths(&["AActor.rhai"])),
+            (
+                PathBuf::from("goblin.moho"),
+                paths(&["AActor.rhai", "base.moho"]),
+            ),
+            (
+                PathBuf::from("orc.moho"),
+                paths(&["AActor.rhai", "base.moho"]),
+            ),
+            (PathBuf::from("other.moho"), paths(&["UENUM.rhai"])),
+        ]);
+
+        // a .moho file regenerates itself and the files that extend its classes
+        assert_eq!(
+            dirty_files(
+                &paths(&["base.moho"]),
+                &moho_files,
+                &dependencies,
+                &BTreeSet::new()
+            ),
+            paths(&["base.moho", "goblin.moho", "orc.moho"])
+        );
+
+        // a script regenerates every file that used it
+        assert_eq!(
+            dirty_files(
+                &paths(&["UENUM.rhai"]),
+                &moho_files,
+                &dependencies,
+                &BTreeSet::new()
+            ),
+            paths(&["other.moho"])
+        );
+
+        // deleted files and files redefining a name aren't generated
+        assert_eq!(
+            dirty_files(
+                &paths(&["base.moho", "gone.moho"]),
+                &moho_files,
+                &dependencies,
+                &paths(&["orc.moho"])
+            ),
+            paths(&["base.moho", "goblin.moho"])
+        );
+    }
+}
@@ -1,20 +1,43 @@
 #include "ATestActor.h"
+#include "GameFramework/Pawn.h"
+
+// moho:begin user Includes
+// moho:end
 
 // Sets default values
 ATestActor::ATestActor()
 {
     // Set this actor to call Tick() every frame.  You can turn this off to improve performance if you don't need it.
     PrimaryActorTick.bCanEverTick = true;
+
+    // moho:begin user Constructor
+    // moho:end
 }
 
 // Called when the game starts or when spawned
 void ATestActor::BeginPlay()
 {
     Super::BeginPlay();
+
+    // moho:begin user BeginPlay
+    // moho:end
 }
 
 // Called every frame
 void ATestActor::Tick(float DeltaTime)
 {
     Super::Tick(DeltaTime);
-}
\ No newline at end of file
+
+    // moho:begin user Tick
+    // moho:end
+}
+
+APawn* ATestActor::GetPlayerPawn(const UObject* WorldContextObject, int32 PlayerIndex)
+{
+    // moho:begin user GetPlayerPawn
+    return {};
+    // moho:end
+}
+
+// moho:begin user Definitions
+// moho:end
\ No newline at end of file
@@ -3,6 +3,8 @@
 #include "CoreMinimal.h"
 #include "GameFramework/Actor.h"
 
+class APawn;
+
 UCLASS()
 class ATestActor : public AActor
 {
@@ -19,20 +21,18 @@ private:
 	// Called every frame
 	virtual void Tick(float DeltaTime) override;
 
-protected:
-    UPROPERTY(EditAnywhere, BlueprintReadWrite, Category="Object References", Description="A nice long description goes here")
-    AAnotherActor m_AnotherActor;
-
-
-
 public:
-	AAnotherActor& GetAnotherActor() const { return m_AnotherActor; }
 	const AAnotherActor& GetAnotherActor() const { return m_AnotherActor; }
-	void SetAnotherActor(AAnotherActor& value) { m_AnotherActor = value; }
-
+	void SetAnotherActor(const AAnotherActor& Value)
+	{
+		m_AnotherActor = Value;
+	}
 
     UFUNCTION(BlueprintPure, Category="Game", meta=(WorldContext="WorldContextObject", UnsafeDuringActorConstruction="true"))
-    APawn* GetPlayerPawn(const UObject* WorldContextObject, int32 PlayerIndex);
+    static APawn* GetPlayerPawn(const UObject* WorldContextObject, int32 PlayerIndex);
 
+protected:
+    UPROPERTY(EditAnywhere, BlueprintReadWrite, Category="Object References", Description="A nice long description goes here")
+    AAnotherActor m_AnotherActor;
 
 };
\ No newline at end of file
@@ -0,0 +1,12 @@
+#pragma once
+
+#include "CoreMinimal.h"
+#include "EWeaponType.generated.h"
+
+UENUM(BlueprintType)
+enum class EWeaponType : uint8
+{
+    Melee UMETA(DisplayName="Melee Weapon"),
+    Ranged = 3,
+    Thrown UMETA(DisplayName="Thrown", Hidden),
+};
\ No newline at end of file
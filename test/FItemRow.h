@@ -0,0 +1,25 @@
+#pragma once
+
+#include "CoreMinimal.h"
+#include "Engine/DataTable.h"
+#include "FItemRow.generated.h"
+
+USTRUCT(BlueprintType)
+struct FItemRow : public FTableRowBase
+{
+	GENERATED_BODY()
+
+public:
+    UPROPERTY(EditAnywhere, BlueprintReadWrite, Category="Item")
+    float Weight = 1;
+
+    UPROPERTY(EditAnywhere, BlueprintReadWrite, Category="Item")
+    int Price;
+
+    UPROPERTY(EditAnywhere, BlueprintReadWrite, Category="Item")
+    TMap<FName, int32> Modifiers;
+
+    UPROPERTY(VisibleAnywhere)
+    bool Stackable = false;
+
+};
\ No newline at end of file